@@ -0,0 +1,88 @@
+use std::{error::Error, fmt, str::FromStr};
+
+/// A malformed or truncated puzzle input.
+///
+/// Lines and columns are 1-indexed, and columns count characters rather than bytes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub day: u32,
+    pub line: usize,
+    pub column: usize,
+    pub expected: String,
+    pub found: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "day {}, line {}, column {}: expected {}, found {}",
+            self.day, self.line, self.column, self.expected, self.found
+        )
+    }
+}
+
+impl Error for ParseError {}
+
+/// The input being parsed by a generator, used to locate errors within it.
+#[derive(Debug, Clone, Copy)]
+pub struct ParseContext<'a> {
+    day: u32,
+    input: &'a str,
+}
+
+impl<'a> ParseContext<'a> {
+    pub fn new(day: u32, input: &'a str) -> Self {
+        Self { day, input }
+    }
+
+    /// Creates an error pointing at the start of `at`, which should be a slice of the input.
+    /// Anything else is reported at the start of the input.
+    pub fn error(&self, at: &str, expected: impl Into<String>) -> ParseError {
+        let offset = (at.as_ptr() as usize)
+            .checked_sub(self.input.as_ptr() as usize)
+            .filter(|&offset| offset <= self.input.len())
+            .unwrap_or(0);
+        let before = &self.input[..offset];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        let token = self.input[offset..].lines().next().unwrap_or("");
+        let found = if !token.is_empty() {
+            format!("{token:?}")
+        } else if offset == self.input.len() {
+            "end of input".to_string()
+        } else {
+            "end of line".to_string()
+        };
+        ParseError {
+            day: self.day,
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            expected: expected.into(),
+            found,
+        }
+    }
+
+    /// Creates an error pointing just past the end of `after`, e.g. for a missing delimiter.
+    pub fn error_after(&self, after: &str, expected: impl Into<String>) -> ParseError {
+        self.error(&after[after.len()..], expected)
+    }
+
+    /// Parses a whole token as a number.
+    pub fn number<T: FromStr>(&self, token: &str) -> Result<T, ParseError> {
+        token.parse().map_err(|_| self.error(token, "a number"))
+    }
+
+    pub fn split_once(
+        &self,
+        s: &'a str,
+        delimiter: &str,
+    ) -> Result<(&'a str, &'a str), ParseError> {
+        s.split_once(delimiter)
+            .ok_or_else(|| self.error_after(s, format!("{delimiter:?}")))
+    }
+
+    pub fn strip_prefix(&self, s: &'a str, prefix: &str) -> Result<&'a str, ParseError> {
+        s.strip_prefix(prefix)
+            .ok_or_else(|| self.error(s, format!("{prefix:?}")))
+    }
+}
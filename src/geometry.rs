@@ -0,0 +1,333 @@
+//! Points, directions and bounding boxes for the spatial days.
+//!
+//! Like the grids, `y` grows downwards, so [`Direction::Up`] decreases it.
+
+use std::ops::{Add, AddAssign, Mul, Neg, Sub, SubAssign};
+
+use num::{Signed, ToPrimitive};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point2<T> {
+    pub x: T,
+    pub y: T,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Point3<T> {
+    pub x: T,
+    pub y: T,
+    pub z: T,
+}
+
+impl<T> Point2<T> {
+    pub const fn new(x: T, y: T) -> Self {
+        Point2 { x, y }
+    }
+}
+
+impl<T> Point3<T> {
+    pub const fn new(x: T, y: T, z: T) -> Self {
+        Point3 { x, y, z }
+    }
+}
+
+impl<T: Signed + Copy + Ord> Point2<T> {
+    /// The sum of the absolute coordinates, i.e. the distance in orthogonal steps.
+    pub fn manhattan(self) -> T {
+        self.x.abs() + self.y.abs()
+    }
+
+    /// The largest absolute coordinate, i.e. the distance in king's moves.
+    pub fn chebyshev(self) -> T {
+        self.x.abs().max(self.y.abs())
+    }
+
+    /// A step of at most one unit along each axis towards this point.
+    pub fn signum(self) -> Self {
+        Point2::new(self.x.signum(), self.y.signum())
+    }
+
+    pub fn manhattan_distance(self, other: Self) -> T {
+        (self - other).manhattan()
+    }
+
+    pub fn chebyshev_distance(self, other: Self) -> T {
+        (self - other).chebyshev()
+    }
+}
+
+impl<T: Signed + Copy + Ord> Point3<T> {
+    pub fn manhattan(self) -> T {
+        self.x.abs() + self.y.abs() + self.z.abs()
+    }
+
+    pub fn chebyshev(self) -> T {
+        self.x.abs().max(self.y.abs()).max(self.z.abs())
+    }
+
+    pub fn signum(self) -> Self {
+        Point3::new(self.x.signum(), self.y.signum(), self.z.signum())
+    }
+
+    pub fn manhattan_distance(self, other: Self) -> T {
+        (self - other).manhattan()
+    }
+
+    pub fn chebyshev_distance(self, other: Self) -> T {
+        (self - other).chebyshev()
+    }
+
+    /// The unit vectors towards the six faces of a cube.
+    pub fn faces() -> [Self; 6] {
+        let (o, i) = (T::zero(), T::one());
+        [
+            Point3::new(i, o, o),
+            Point3::new(-i, o, o),
+            Point3::new(o, i, o),
+            Point3::new(o, -i, o),
+            Point3::new(o, o, i),
+            Point3::new(o, o, -i),
+        ]
+    }
+}
+
+impl<T: ToPrimitive + Copy> Point2<T> {
+    /// The straight line length, for when that's what the puzzle actually wants.
+    pub fn euclidean(self) -> f64 {
+        let (x, y) = (self.x.to_f64().unwrap(), self.y.to_f64().unwrap());
+        x.hypot(y)
+    }
+}
+
+impl<T: ToPrimitive + Copy> Point3<T> {
+    pub fn euclidean(self) -> f64 {
+        let (x, y, z) = (
+            self.x.to_f64().unwrap(),
+            self.y.to_f64().unwrap(),
+            self.z.to_f64().unwrap(),
+        );
+        (x * x + y * y + z * z).sqrt()
+    }
+}
+
+impl<T> From<(T, T)> for Point2<T> {
+    fn from((x, y): (T, T)) -> Self {
+        Point2::new(x, y)
+    }
+}
+
+impl<T> From<Point2<T>> for (T, T) {
+    fn from(point: Point2<T>) -> Self {
+        (point.x, point.y)
+    }
+}
+
+impl<T> From<(T, T, T)> for Point3<T> {
+    fn from((x, y, z): (T, T, T)) -> Self {
+        Point3::new(x, y, z)
+    }
+}
+
+impl<T> From<Point3<T>> for (T, T, T) {
+    fn from(point: Point3<T>) -> Self {
+        (point.x, point.y, point.z)
+    }
+}
+
+// Component-wise arithmetic, and scaling by a single number
+macro_rules! impl_ops {
+    ($point:ident { $($field:ident),* }) => {
+        impl<T: Add<Output = T>> Add for $point<T> {
+            type Output = Self;
+
+            fn add(self, rhs: Self) -> Self::Output {
+                $point { $($field: self.$field + rhs.$field),* }
+            }
+        }
+
+        impl<T: Sub<Output = T>> Sub for $point<T> {
+            type Output = Self;
+
+            fn sub(self, rhs: Self) -> Self::Output {
+                $point { $($field: self.$field - rhs.$field),* }
+            }
+        }
+
+        impl<T: Copy + Mul<Output = T>> Mul<T> for $point<T> {
+            type Output = Self;
+
+            fn mul(self, rhs: T) -> Self::Output {
+                $point { $($field: self.$field * rhs),* }
+            }
+        }
+
+        impl<T: Neg<Output = T>> Neg for $point<T> {
+            type Output = Self;
+
+            fn neg(self) -> Self::Output {
+                $point { $($field: -self.$field),* }
+            }
+        }
+
+        impl<T: Copy + Add<Output = T>> AddAssign for $point<T> {
+            fn add_assign(&mut self, rhs: Self) {
+                *self = *self + rhs;
+            }
+        }
+
+        impl<T: Copy + Sub<Output = T>> SubAssign for $point<T> {
+            fn sub_assign(&mut self, rhs: Self) {
+                *self = *self - rhs;
+            }
+        }
+    };
+}
+
+impl_ops!(Point2 { x, y });
+impl_ops!(Point3 { x, y, z });
+
+/// The four orthogonal directions, in clockwise order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn turn_right(self) -> Self {
+        Self::ALL[(self as usize + 1) % 4]
+    }
+
+    pub fn turn_left(self) -> Self {
+        Self::ALL[(self as usize + 3) % 4]
+    }
+
+    pub fn reverse(self) -> Self {
+        Self::ALL[(self as usize + 2) % 4]
+    }
+
+    /// A single step in this direction.
+    pub fn delta<T: Signed>(self) -> Point2<T> {
+        match self {
+            Direction::Up => Point2::new(T::zero(), -T::one()),
+            Direction::Right => Point2::new(T::one(), T::zero()),
+            Direction::Down => Point2::new(T::zero(), T::one()),
+            Direction::Left => Point2::new(-T::one(), T::zero()),
+        }
+    }
+}
+
+/// An axis-aligned bounding box, inclusive at both corners.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Aabb<T> {
+    pub min: Point2<T>,
+    pub max: Point2<T>,
+}
+
+impl<T: Copy + Ord> Aabb<T> {
+    /// The smallest box containing every point, or `None` if there are none.
+    pub fn from_points(points: impl IntoIterator<Item = Point2<T>>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        Some(points.fold(
+            Aabb {
+                min: first,
+                max: first,
+            },
+            Aabb::expand,
+        ))
+    }
+
+    /// Grows the box just enough to contain a point.
+    pub fn expand(self, point: Point2<T>) -> Self {
+        Aabb {
+            min: Point2::new(self.min.x.min(point.x), self.min.y.min(point.y)),
+            max: Point2::new(self.max.x.max(point.x), self.max.y.max(point.y)),
+        }
+    }
+
+    pub fn contains(&self, point: Point2<T>) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+}
+
+impl<T: Copy + Ord + Signed> Aabb<T> {
+    pub fn width(&self) -> T {
+        self.max.x - self.min.x + T::one()
+    }
+
+    pub fn height(&self) -> T {
+        self.max.y - self.min.y + T::one()
+    }
+
+    pub fn area(&self) -> T {
+        self.width() * self.height()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn metrics() {
+        let p = Point2::new(3, -4);
+        assert_eq!(p.manhattan(), 7);
+        assert_eq!(p.chebyshev(), 4);
+        assert_eq!(p.euclidean(), 5.0);
+        assert_eq!(p.signum(), Point2::new(1, -1));
+        assert_eq!(Point2::new(1, 1).manhattan_distance(Point2::new(-1, 2)), 3);
+
+        let q = Point3::new(-2, 3, 6);
+        assert_eq!(q.manhattan(), 11);
+        assert_eq!(q.chebyshev(), 6);
+        assert_eq!(q.euclidean(), 7.0);
+    }
+
+    #[test]
+    fn arithmetic() {
+        let mut p = Point2::new(1, 2);
+        p += Point2::new(3, 4) * 2;
+        assert_eq!(p, Point2::new(7, 10));
+        assert_eq!(-p - Point2::new(1, 1), Point2::new(-8, -11));
+        assert_eq!(
+            Point3::<i8>::faces()
+                .into_iter()
+                .fold(Point3::default(), Add::add),
+            Point3::default()
+        );
+    }
+
+    #[test]
+    fn directions() {
+        assert_eq!(Direction::Up.turn_right(), Direction::Right);
+        assert_eq!(Direction::Up.turn_left(), Direction::Left);
+        assert_eq!(Direction::Left.reverse(), Direction::Right);
+        for direction in Direction::ALL {
+            assert_eq!(direction.turn_left().turn_right(), direction);
+            assert_eq!(direction.reverse().delta::<i32>(), -direction.delta());
+        }
+        assert_eq!(Direction::Up.delta::<i32>(), Point2::new(0, -1));
+    }
+
+    #[test]
+    fn bounding_box() {
+        let aabb =
+            Aabb::from_points([Point2::new(2, -1), Point2::new(-3, 4), Point2::new(0, 0)]).unwrap();
+        assert_eq!(aabb.min, Point2::new(-3, -1));
+        assert_eq!(aabb.max, Point2::new(2, 4));
+        assert_eq!(aabb.area(), 36);
+        assert!(aabb.contains(Point2::new(2, 4)));
+        assert!(!aabb.contains(Point2::new(3, 0)));
+        assert_eq!(Aabb::<i32>::from_points([]), None);
+    }
+}
@@ -0,0 +1,450 @@
+//! Two-dimensional grids, as used by the days whose input is a picture.
+//!
+//! Positions in a [`Grid`] are `(x, y)` pairs, and those in a [`SparseGrid`] are [`Point2`]s.
+//! Either way, `y` grows downwards like the lines of the input.
+
+use std::{
+    collections::hash_map::Entry,
+    fmt,
+    ops::{Index, IndexMut},
+};
+
+use fxhash::FxHashMap;
+
+use crate::{
+    error::{ParseContext, ParseError},
+    geometry::{Aabb, Point2},
+};
+
+/// A dense, fixed size grid stored row by row.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    cells: Vec<T>,
+    width: usize,
+    height: usize,
+}
+
+impl<T> Grid<T> {
+    pub fn new(width: usize, height: usize, fill: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: vec![fill; width * height],
+            width,
+            height,
+        }
+    }
+
+    /// Builds a grid from its cells in row-major order.
+    pub fn from_vec(width: usize, cells: Vec<T>) -> Self {
+        assert!(
+            width != 0 && cells.len().is_multiple_of(width),
+            "{} cells don't fit in rows of {width}",
+            cells.len()
+        );
+        Grid {
+            height: cells.len() / width,
+            cells,
+            width,
+        }
+    }
+
+    /// Parses one cell per character. Every line must be as long as the first, and
+    /// `cell` returns `None` for characters that aren't allowed.
+    pub fn parse(
+        ctx: &ParseContext,
+        input: &str,
+        expected: &str,
+        cell: impl FnMut(u8) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        Self::parse_rows(ctx, input, expected, None, cell)
+    }
+
+    /// Like [`Grid::parse`], but lines may have different lengths. Shorter lines are
+    /// padded with `fill` up to the longest one.
+    pub fn parse_padded(
+        ctx: &ParseContext,
+        input: &str,
+        expected: &str,
+        fill: T,
+        cell: impl FnMut(u8) -> Option<T>,
+    ) -> Result<Self, ParseError>
+    where
+        T: Clone,
+    {
+        Self::parse_rows(ctx, input, expected, Some(&|| fill.clone()), cell)
+    }
+
+    fn parse_rows(
+        ctx: &ParseContext,
+        input: &str,
+        expected: &str,
+        fill: Option<&dyn Fn() -> T>,
+        mut cell: impl FnMut(u8) -> Option<T>,
+    ) -> Result<Self, ParseError> {
+        let width = match fill {
+            Some(_) => input.lines().map(str::len).max(),
+            None => input.lines().next().map(str::len),
+        }
+        .unwrap_or(0);
+        let mut cells = Vec::with_capacity(width * input.lines().count());
+        for line in input.lines() {
+            if fill.is_none() && line.len() != width {
+                return Err(ctx.error_after(
+                    &line[..line.len().min(width)],
+                    format!("a row of {width} cells"),
+                ));
+            }
+            for (x, b) in line.bytes().enumerate() {
+                cells.push(cell(b).ok_or_else(|| ctx.error(&line[x..], expected))?);
+            }
+            if let Some(fill) = fill {
+                cells.extend(std::iter::repeat_with(fill).take(width - line.len()));
+            }
+        }
+        if width == 0 {
+            return Err(ctx.error(input, expected));
+        }
+        Ok(Grid::from_vec(width, cells))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    pub fn get(&self, position: (usize, usize)) -> Option<&T> {
+        self.contains(position)
+            .then(|| &self.cells[position.1 * self.width + position.0])
+    }
+
+    pub fn get_mut(&mut self, position: (usize, usize)) -> Option<&mut T> {
+        self.contains(position)
+            .then(|| &mut self.cells[position.1 * self.width + position.0])
+    }
+
+    /// Looks up a position that may lie off the grid, e.g. one step past its edge.
+    pub fn get_signed(&self, (x, y): (isize, isize)) -> Option<&T> {
+        self.get((usize::try_from(x).ok()?, usize::try_from(y).ok()?))
+    }
+
+    pub fn row(&self, y: usize) -> &[T] {
+        &self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn row_mut(&mut self, y: usize) -> &mut [T] {
+        &mut self.cells[y * self.width..(y + 1) * self.width]
+    }
+
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> {
+        self.cells.chunks(self.width)
+    }
+
+    pub fn rows_mut(&mut self) -> impl DoubleEndedIterator<Item = &mut [T]> {
+        self.cells.chunks_mut(self.width)
+    }
+
+    pub fn column(&self, x: usize) -> impl DoubleEndedIterator<Item = &T> {
+        self.cells[x..].iter().step_by(self.width)
+    }
+
+    pub fn columns(&self) -> impl Iterator<Item = impl DoubleEndedIterator<Item = &T>> {
+        (0..self.width).map(|x| self.column(x))
+    }
+
+    /// Every position of the grid, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        (0..self.height).flat_map(move |y| (0..width).map(move |x| (x, y)))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        self.positions().zip(&self.cells)
+    }
+
+    /// Finds the first position, row by row, whose cell matches.
+    pub fn position(&self, predicate: impl FnMut(&T) -> bool) -> Option<(usize, usize)> {
+        let index = self.cells.iter().position(predicate)?;
+        Some((index % self.width, index / self.width))
+    }
+
+    /// The orthogonally adjacent positions that lie within the grid.
+    pub fn neighbors4(
+        &self,
+        (x, y): (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + use<'_, T> {
+        [(0, -1), (1, 0), (0, 1), (-1, 0)]
+            .into_iter()
+            .filter_map(move |(dx, dy)| self.offset((x, y), dx, dy))
+    }
+
+    /// The orthogonally and diagonally adjacent positions that lie within the grid.
+    pub fn neighbors8(
+        &self,
+        (x, y): (usize, usize),
+    ) -> impl Iterator<Item = (usize, usize)> + use<'_, T> {
+        [
+            (-1, -1),
+            (0, -1),
+            (1, -1),
+            (1, 0),
+            (1, 1),
+            (0, 1),
+            (-1, 1),
+            (-1, 0),
+        ]
+        .into_iter()
+        .filter_map(move |(dx, dy)| self.offset((x, y), dx, dy))
+    }
+
+    fn offset(&self, (x, y): (usize, usize), dx: isize, dy: isize) -> Option<(usize, usize)> {
+        let position = (x.checked_add_signed(dx)?, y.checked_add_signed(dy)?);
+        self.contains(position).then_some(position)
+    }
+
+    /// Swaps rows for columns.
+    pub fn transpose(&self) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            cells: self.columns().flatten().cloned().collect(),
+            width: self.height,
+            height: self.width,
+        }
+    }
+
+    pub fn map<U>(&self, f: impl FnMut(&T) -> U) -> Grid<U> {
+        Grid {
+            cells: self.cells.iter().map(f).collect(),
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+impl<T> Index<(usize, usize)> for Grid<T> {
+    type Output = T;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) is outside of a {}x{} grid",
+            self.width,
+            self.height
+        );
+        &self.cells[y * self.width + x]
+    }
+}
+
+impl<T> IndexMut<(usize, usize)> for Grid<T> {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Self::Output {
+        assert!(
+            x < self.width && y < self.height,
+            "({x}, {y}) is outside of a {}x{} grid",
+            self.width,
+            self.height
+        );
+        &mut self.cells[y * self.width + x]
+    }
+}
+
+/// Draws grids of characters, e.g. for debugging
+impl fmt::Display for Grid<u8> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (y, row) in self.rows().enumerate() {
+            if y != 0 {
+                writeln!(f)?;
+            }
+            f.write_str(&String::from_utf8_lossy(row))?;
+        }
+        Ok(())
+    }
+}
+
+/// An unbounded grid that only stores the cells which have been set.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SparseGrid<T> {
+    cells: FxHashMap<Point2<isize>, T>,
+}
+
+impl<T> Default for SparseGrid<T> {
+    fn default() -> Self {
+        SparseGrid {
+            cells: FxHashMap::default(),
+        }
+    }
+}
+
+impl<T> SparseGrid<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.cells.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.is_empty()
+    }
+
+    pub fn contains(&self, position: Point2<isize>) -> bool {
+        self.cells.contains_key(&position)
+    }
+
+    pub fn get(&self, position: Point2<isize>) -> Option<&T> {
+        self.cells.get(&position)
+    }
+
+    pub fn get_mut(&mut self, position: Point2<isize>) -> Option<&mut T> {
+        self.cells.get_mut(&position)
+    }
+
+    pub fn insert(&mut self, position: Point2<isize>, value: T) -> Option<T> {
+        self.cells.insert(position, value)
+    }
+
+    pub fn entry(&mut self, position: Point2<isize>) -> Entry<'_, Point2<isize>, T> {
+        self.cells.entry(position)
+    }
+
+    pub fn remove(&mut self, position: Point2<isize>) -> Option<T> {
+        self.cells.remove(&position)
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear()
+    }
+
+    /// The set positions, in no particular order.
+    pub fn positions(&self) -> impl Iterator<Item = Point2<isize>> + '_ {
+        self.cells.keys().copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Point2<isize>, &T)> {
+        self.cells
+            .iter()
+            .map(|(&position, value)| (position, value))
+    }
+
+    /// The smallest box containing every set position, or `None` if there are none.
+    pub fn bounds(&self) -> Option<Aabb<isize>> {
+        Aabb::from_points(self.positions())
+    }
+}
+
+impl<T> FromIterator<(Point2<isize>, T)> for SparseGrid<T> {
+    fn from_iter<I: IntoIterator<Item = (Point2<isize>, T)>>(iter: I) -> Self {
+        SparseGrid {
+            cells: iter.into_iter().collect(),
+        }
+    }
+}
+
+/// The orthogonally adjacent positions of an unbounded grid.
+pub fn neighbors4(Point2 { x, y }: Point2<isize>) -> [Point2<isize>; 4] {
+    [(x, y - 1), (x + 1, y), (x, y + 1), (x - 1, y)].map(Point2::from)
+}
+
+/// The orthogonally and diagonally adjacent positions of an unbounded grid.
+pub fn neighbors8(Point2 { x, y }: Point2<isize>) -> [Point2<isize>; 8] {
+    [
+        (x - 1, y - 1),
+        (x, y - 1),
+        (x + 1, y - 1),
+        (x + 1, y),
+        (x + 1, y + 1),
+        (x, y + 1),
+        (x - 1, y + 1),
+        (x - 1, y),
+    ]
+    .map(Point2::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn example() -> Grid<u8> {
+        let ctx = ParseContext::new(0, "abc\ndef");
+        Grid::parse(&ctx, "abc\ndef", "a letter", |b| {
+            b.is_ascii_lowercase().then_some(b)
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn indexing() {
+        let grid = example();
+        assert_eq!((grid.width(), grid.height()), (3, 2));
+        assert_eq!(grid[(2, 0)], b'c');
+        assert_eq!(grid[(0, 1)], b'd');
+        assert_eq!(grid.get((3, 0)), None);
+        assert_eq!(grid.get_signed((-1, 0)), None);
+        assert_eq!(grid.position(|&b| b == b'e'), Some((1, 1)));
+    }
+
+    #[test]
+    fn rows_and_columns() {
+        let grid = example();
+        assert_eq!(grid.row(1), b"def");
+        assert_eq!(grid.column(1).copied().collect::<Vec<_>>(), b"be");
+        let transposed = grid.transpose();
+        assert_eq!(transposed.to_string(), "ad\nbe\ncf");
+        assert_eq!(transposed.transpose(), grid);
+    }
+
+    #[test]
+    fn neighbors() {
+        let grid = example();
+        let mut around_corner: Vec<_> = grid.neighbors4((0, 0)).collect();
+        around_corner.sort();
+        assert_eq!(around_corner, [(0, 1), (1, 0)]);
+        assert_eq!(grid.neighbors8((1, 0)).count(), 5);
+        assert_eq!(grid.neighbors8((1, 1)).count(), 5);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let input = "ab\nabc";
+        let ctx = ParseContext::new(0, input);
+        let error = Grid::parse(&ctx, input, "a letter", Some).unwrap_err();
+        assert_eq!((error.line, error.column), (2, 3));
+        let input = "ab\na!";
+        let ctx = ParseContext::new(0, input);
+        let error = Grid::parse(&ctx, input, "a letter", |b| {
+            b.is_ascii_lowercase().then_some(b)
+        })
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (2, 2));
+
+        let input = "a\nabc";
+        let ctx = ParseContext::new(0, input);
+        let grid = Grid::parse_padded(&ctx, input, "a letter", b' ', Some).unwrap();
+        assert_eq!(grid.to_string(), "a  \nabc");
+    }
+
+    #[test]
+    fn sparse() {
+        let grid: SparseGrid<()> = [(Point2::new(-2, 1), ()), (Point2::new(3, -4), ())]
+            .into_iter()
+            .collect();
+        assert!(grid.contains(Point2::new(-2, 1)));
+        assert!(!grid.contains(Point2::new(0, 0)));
+        let bounds = grid.bounds().unwrap();
+        assert_eq!(
+            (bounds.min, bounds.max),
+            (Point2::new(-2, -4), Point2::new(3, 1))
+        );
+        assert_eq!(SparseGrid::<()>::new().bounds(), None);
+    }
+}
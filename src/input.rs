@@ -0,0 +1,44 @@
+use std::borrow::Cow;
+
+/// Brings an input file into the layout every generator expects, no matter
+/// which platform or editor it was saved with:
+///
+/// - `\r\n` line endings become `\n`
+/// - trailing whitespace is trimmed from every line
+/// - trailing blank lines are dropped, along with the final newline if there is one
+/// - a final `@` is dropped: some inputs were saved with it in place of the last
+///   newline, so that every line came out the same length
+///
+/// Leading whitespace is significant for some days, so it is left alone.
+pub fn normalize(input: &str) -> Cow<'_, str> {
+    let input = input.strip_suffix('@').unwrap_or(input);
+    let is_normal = !input.contains('\r')
+        && !input.ends_with(char::is_whitespace)
+        && input
+            .lines()
+            .all(|line| !line.ends_with(char::is_whitespace));
+    if is_normal {
+        return Cow::Borrowed(input);
+    }
+
+    let mut normalized = String::with_capacity(input.len());
+    for line in input.lines() {
+        normalized.push_str(line.trim_end());
+        normalized.push('\n');
+    }
+    normalized.truncate(normalized.trim_end().len());
+    Cow::Owned(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalizing() {
+        assert!(matches!(normalize("A Y\nB X"), Cow::Borrowed("A Y\nB X")));
+        assert_eq!(normalize("A Y \r\nB X\r\n\n"), "A Y\nB X");
+        assert!(matches!(normalize("A Y\nB X@"), Cow::Borrowed("A Y\nB X")));
+        assert_eq!(normalize("  indented\t\n"), "  indented");
+    }
+}
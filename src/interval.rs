@@ -0,0 +1,308 @@
+//! Sets of integers kept as sorted, disjoint intervals, for the days that deal in spans.
+//!
+//! Intervals include both of their ends, the way the puzzles write them: `2-4` is three
+//! sections. Exclusive ranges can be converted with [`Interval::exclusive`].
+
+use std::ops::RangeInclusive;
+
+use num::PrimInt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Interval<T> {
+    pub start: T,
+    pub end: T,
+}
+
+impl<T: PrimInt> Interval<T> {
+    /// Both ends are included, so `start` can't be after `end`.
+    pub fn new(start: T, end: T) -> Self {
+        debug_assert!(start <= end, "intervals can't be empty");
+        Interval { start, end }
+    }
+
+    pub fn unit(x: T) -> Self {
+        Interval { start: x, end: x }
+    }
+
+    /// The interval from `start` up to but not including `end`, or `None` if that's nothing.
+    pub fn exclusive(start: T, end: T) -> Option<Self> {
+        (start < end).then(|| Interval::new(start, end - T::one()))
+    }
+
+    pub fn len(&self) -> T {
+        self.end - self.start + T::one()
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.start <= x && x <= self.end
+    }
+
+    pub fn contains_interval(&self, other: &Self) -> bool {
+        self.start <= other.start && other.end <= self.end
+    }
+
+    pub fn overlaps(&self, other: &Self) -> bool {
+        self.start <= other.end && other.start <= self.end
+    }
+
+    pub fn intersection(&self, other: &Self) -> Option<Self> {
+        self.overlaps(other)
+            .then(|| Interval::new(self.start.max(other.start), self.end.min(other.end)))
+    }
+
+    /// Whether this interval ends at least one number before `other` starts, so that the
+    /// two can't be merged.
+    fn apart_before(&self, other: &Self) -> bool {
+        self.end.saturating_add(T::one()) < other.start
+    }
+}
+
+impl<T: PrimInt> From<RangeInclusive<T>> for Interval<T> {
+    fn from(range: RangeInclusive<T>) -> Self {
+        Interval::new(*range.start(), *range.end())
+    }
+}
+
+/// Any set of integers, as the fewest intervals that cover it exactly.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct IntervalSet<T> {
+    /// Sorted, with at least one number missing between each of them
+    intervals: Vec<Interval<T>>,
+}
+
+impl<T> Default for IntervalSet<T> {
+    fn default() -> Self {
+        IntervalSet { intervals: vec![] }
+    }
+}
+
+impl<T: PrimInt> IntervalSet<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds every number in the interval, merging it with any intervals it overlaps or
+    /// touches.
+    pub fn insert(&mut self, interval: Interval<T>) {
+        let first = self
+            .intervals
+            .partition_point(|x| x.apart_before(&interval));
+        let last = self
+            .intervals
+            .partition_point(|x| !interval.apart_before(x));
+        if first == last {
+            self.intervals.insert(first, interval);
+            return;
+        }
+        let end = self.intervals[last - 1].end.max(interval.end);
+        let merged = &mut self.intervals[first];
+        merged.start = merged.start.min(interval.start);
+        merged.end = end;
+        self.intervals.drain(first + 1..last);
+    }
+
+    /// Takes out every number in the interval.
+    pub fn remove(&mut self, interval: Interval<T>) {
+        *self = self.difference(&IntervalSet::from_iter([interval]));
+    }
+
+    pub fn clear(&mut self) {
+        self.intervals.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// How many numbers there are in the set.
+    pub fn len(&self) -> T {
+        (self.intervals.iter()).fold(T::zero(), |total, interval| total + interval.len())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Interval<T>> {
+        self.intervals.iter()
+    }
+
+    pub fn contains(&self, x: T) -> bool {
+        self.find(&Interval::unit(x))
+            .is_some_and(|interval| interval.contains(x))
+    }
+
+    /// Whether every number in the interval is in the set.
+    pub fn contains_interval(&self, interval: &Interval<T>) -> bool {
+        self.find(interval)
+            .is_some_and(|found| found.contains_interval(interval))
+    }
+
+    /// Whether any number in the interval is in the set.
+    pub fn overlaps(&self, interval: &Interval<T>) -> bool {
+        self.find(interval)
+            .is_some_and(|found| found.overlaps(interval))
+    }
+
+    /// The first of the set's intervals that doesn't end before `interval` starts
+    fn find(&self, interval: &Interval<T>) -> Option<&Interval<T>> {
+        let i = self.intervals.partition_point(|x| x.end < interval.start);
+        self.intervals.get(i)
+    }
+
+    pub fn union(&self, other: &Self) -> Self {
+        self.iter().chain(other.iter()).copied().collect()
+    }
+
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut intervals = vec![];
+        let (mut i, mut j) = (0, 0);
+        while let (Some(a), Some(b)) = (self.intervals.get(i), other.intervals.get(j)) {
+            intervals.extend(a.intersection(b));
+            // whichever ends first can't overlap anything else
+            if a.end < b.end {
+                i += 1;
+            } else {
+                j += 1;
+            }
+        }
+        IntervalSet { intervals }
+    }
+
+    pub fn difference(&self, other: &Self) -> Self {
+        match (self.intervals.first(), self.intervals.last()) {
+            (Some(first), Some(last)) => {
+                let hull = Interval::new(first.start, last.end);
+                self.intersection(&other.complement(hull))
+            }
+            _ => IntervalSet::new(),
+        }
+    }
+
+    /// Every number within `bounds` that isn't in the set.
+    pub fn complement(&self, bounds: Interval<T>) -> Self {
+        IntervalSet {
+            intervals: self.gaps(bounds).collect(),
+        }
+    }
+
+    /// The parts of `within` that aren't in the set, in order.
+    pub fn gaps(&self, within: Interval<T>) -> impl Iterator<Item = Interval<T>> + '_ {
+        let first = self.intervals.partition_point(|x| x.end < within.start);
+        let mut next = Some(within.start);
+        let mut rest = self.intervals[first..].iter();
+        std::iter::from_fn(move || loop {
+            let start = next?;
+            match rest.next() {
+                Some(interval) if interval.start <= within.end => {
+                    next = interval.end.checked_add(&T::one());
+                    if interval.start > start {
+                        return Some(Interval::new(start, interval.start - T::one()));
+                    }
+                }
+                _ => {
+                    next = None;
+                    return (start <= within.end).then(|| Interval::new(start, within.end));
+                }
+            }
+        })
+    }
+}
+
+impl<T: PrimInt> FromIterator<Interval<T>> for IntervalSet<T> {
+    fn from_iter<I: IntoIterator<Item = Interval<T>>>(iter: I) -> Self {
+        let mut sorted: Vec<_> = iter.into_iter().collect();
+        sorted.sort_unstable_by_key(|interval| interval.start);
+        let mut intervals: Vec<Interval<T>> = Vec::with_capacity(sorted.len());
+        for interval in sorted {
+            match intervals.last_mut() {
+                Some(last) if !last.apart_before(&interval) => {
+                    last.end = last.end.max(interval.end);
+                }
+                _ => intervals.push(interval),
+            }
+        }
+        IntervalSet { intervals }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn set(intervals: &[(i32, i32)]) -> IntervalSet<i32> {
+        intervals
+            .iter()
+            .map(|&(start, end)| Interval::new(start, end))
+            .collect()
+    }
+
+    fn pairs(set: &IntervalSet<i32>) -> Vec<(i32, i32)> {
+        set.iter().map(|i| (i.start, i.end)).collect()
+    }
+
+    #[test]
+    fn intervals() {
+        let interval = Interval::new(2, 4);
+        assert_eq!(interval.len(), 3);
+        assert!(interval.contains(4) && !interval.contains(5));
+        assert!(interval.contains_interval(&Interval::unit(3)));
+        assert!(interval.overlaps(&Interval::new(4, 8)));
+        assert!(!interval.overlaps(&Interval::new(5, 8)));
+        assert_eq!(
+            interval.intersection(&(3..=9).into()),
+            Some(Interval::new(3, 4))
+        );
+        assert_eq!(Interval::exclusive(2, 5), Some(interval));
+        assert_eq!(Interval::exclusive(2, 2), None);
+    }
+
+    #[test]
+    fn building() {
+        let mut merged = set(&[(5, 6), (0, 1), (8, 9)]);
+        assert_eq!(pairs(&merged), vec![(0, 1), (5, 6), (8, 9)]);
+        // touching counts as overlapping
+        merged.insert(Interval::new(2, 4));
+        assert_eq!(pairs(&merged), vec![(0, 6), (8, 9)]);
+        merged.insert(Interval::new(-3, 20));
+        assert_eq!(pairs(&merged), vec![(-3, 20)]);
+        merged.remove(Interval::new(0, 5));
+        assert_eq!(pairs(&merged), vec![(-3, -1), (6, 20)]);
+        assert_eq!(merged.len(), 18);
+        assert_eq!(set(&[(0, 3), (2, 5), (6, 7)]), set(&[(0, 7)]));
+
+        let mut extreme = IntervalSet::new();
+        extreme.insert(Interval::new(u8::MAX, u8::MAX));
+        extreme.insert(Interval::new(0, 254));
+        assert_eq!(extreme.iter().count(), 1);
+    }
+
+    #[test]
+    fn operations() {
+        let a = set(&[(0, 4), (10, 14)]);
+        let b = set(&[(3, 11), (20, 20)]);
+        assert_eq!(pairs(&a.union(&b)), vec![(0, 14), (20, 20)]);
+        assert_eq!(pairs(&a.intersection(&b)), vec![(3, 4), (10, 11)]);
+        assert_eq!(pairs(&a.difference(&b)), vec![(0, 2), (12, 14)]);
+        assert_eq!(pairs(&b.difference(&a)), vec![(5, 9), (20, 20)]);
+        let bounds = Interval::new(-5, 16);
+        assert_eq!(
+            pairs(&a.complement(bounds)),
+            vec![(-5, -1), (5, 9), (15, 16)]
+        );
+        assert_eq!(pairs(&a.complement(Interval::new(1, 3))), vec![]);
+        assert_eq!(
+            a.gaps(Interval::new(4, 12)).collect::<Vec<_>>(),
+            vec![Interval::new(5, 9)]
+        );
+        let everything = Interval::new(i32::MIN, i32::MAX);
+        assert_eq!(set(&[(i32::MIN, i32::MAX)]).gaps(everything).count(), 0);
+    }
+
+    #[test]
+    fn queries() {
+        let a = set(&[(0, 4), (10, 14)]);
+        assert!(a.contains(4) && a.contains(10) && !a.contains(7) && !a.contains(15));
+        assert!(a.contains_interval(&Interval::new(11, 14)));
+        assert!(!a.contains_interval(&Interval::new(3, 10)));
+        assert!(a.overlaps(&Interval::new(3, 10)));
+        assert!(!a.overlaps(&Interval::new(5, 9)));
+        assert!(IntervalSet::<i32>::new().is_empty());
+    }
+}
@@ -1,9 +1,22 @@
 #![feature(iter_array_chunks)]
-#![feature(array_chunks)]
-#![feature(array_windows)]
-#![feature(exclusive_range_pattern)]
+// style lints that came after some of the solutions were written
+#![allow(
+    clippy::enum_variant_names,
+    clippy::implicit_saturating_sub,
+    clippy::manual_pattern_char_comparison
+)]
 #[macro_use]
 extern crate aoc_runner_derive;
-mod year_2022;
+pub mod error;
+pub mod geometry;
+pub mod grid;
+pub mod input;
+pub mod interval;
+pub mod ocr;
+pub mod search;
+pub mod solutions;
+pub mod verify;
+pub mod visualize;
+pub mod year_2022;
 
 aoc_lib! { year = 2022 }
@@ -0,0 +1,231 @@
+use std::{
+    env,
+    error::Error,
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    process,
+    time::Instant,
+};
+
+use aoc22::{
+    solutions::{input_path, Solution, SOLUTIONS},
+    verify::{self, Answers, ANSWERS_PATH},
+    visualize::{self, Ascii, FrameSink, Gif, Png, Ppm, Recorder},
+};
+
+const USAGE: &str = "\
+usage: aoc22 run --day <day> [--part <part>] [--variant <name>] [--input <path>|-]
+       aoc22 run --day <day> --part <part> --visualize <ascii|ppm|png|gif>
+                 [--output <path>] [--every <n>] [--scale <n>] [--input <path>|-]
+       aoc22 verify [--day <day>] [--answers <path>]
+
+`run` runs the solutions for a day, reading input/2022/day<day>.txt by default.
+Passing `--input -` reads the input from stdin instead.
+
+With `--visualize`, `run` records the simulation of days 14, 17, 23 and 24
+instead, keeping one frame out of every `--every` (1 by default). Frames go to
+`--output`, or stdout if there is none; PNG frames are numbered after it. Image
+formats draw each cell as a square of `--scale` pixels (4 by default).
+
+`verify` runs every solution on its input and compares the results with the
+answers recorded in answers.toml.";
+
+struct RunArgs {
+    day: u32,
+    part: Option<u32>,
+    variant: Option<String>,
+    input: Option<String>,
+    visualize: Option<String>,
+    output: Option<String>,
+    every: usize,
+    scale: usize,
+}
+
+fn parse_number(flag: &str, value: Option<String>) -> Result<u32, String> {
+    let value = value.ok_or_else(|| format!("missing value for {flag}"))?;
+    value
+        .parse()
+        .map_err(|_| format!("invalid value for {flag}: {value:?}"))
+}
+
+fn parse_run_args(mut args: impl Iterator<Item = String>) -> Result<RunArgs, String> {
+    let mut day = None;
+    let mut part = None;
+    let mut variant = None;
+    let mut input = None;
+    let mut visualize = None;
+    let mut output = None;
+    let mut every = 1;
+    let mut scale = 4;
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--day" | "-d" => day = Some(parse_number(&flag, args.next())?),
+            "--part" | "-p" => part = Some(parse_number(&flag, args.next())?),
+            "--variant" | "-v" => {
+                variant = Some(args.next().ok_or("missing value for --variant")?);
+            }
+            "--input" | "-i" => input = Some(args.next().ok_or("missing value for --input")?),
+            "--visualize" => {
+                visualize = Some(args.next().ok_or("missing value for --visualize")?);
+            }
+            "--output" | "-o" => output = Some(args.next().ok_or("missing value for --output")?),
+            "--every" => every = parse_number(&flag, args.next())? as usize,
+            "--scale" => scale = parse_number(&flag, args.next())? as usize,
+            _ => return Err(format!("unexpected argument {flag:?}")),
+        }
+    }
+    Ok(RunArgs {
+        day: day.ok_or("--day is required")?,
+        part,
+        variant,
+        input,
+        visualize,
+        output,
+        every,
+        scale,
+    })
+}
+
+struct VerifyArgs {
+    day: Option<u32>,
+    answers: String,
+}
+
+fn parse_verify_args(mut args: impl Iterator<Item = String>) -> Result<VerifyArgs, String> {
+    let mut day = None;
+    let mut answers = ANSWERS_PATH.to_string();
+    while let Some(flag) = args.next() {
+        match flag.as_str() {
+            "--day" | "-d" => day = Some(parse_number(&flag, args.next())?),
+            "--answers" | "-a" => answers = args.next().ok_or("missing value for --answers")?,
+            _ => return Err(format!("unexpected argument {flag:?}")),
+        }
+    }
+    Ok(VerifyArgs { day, answers })
+}
+
+fn read_input(day: u32, path: Option<&str>) -> io::Result<String> {
+    match path {
+        Some("-") => {
+            let mut buffer = String::new();
+            io::stdin().read_to_string(&mut buffer)?;
+            Ok(buffer)
+        }
+        Some(path) => fs::read_to_string(path),
+        None => fs::read_to_string(input_path(day)),
+    }
+}
+
+fn label(solution: &Solution) -> String {
+    match solution.variant {
+        Some(variant) => format!("Day {} - Part {} - {variant}", solution.day, solution.part),
+        None => format!("Day {} - Part {}", solution.day, solution.part),
+    }
+}
+
+fn watch(args: &RunArgs, format: &str) -> Result<(), Box<dyn Error>> {
+    let simulation = visualize::find(args.day)
+        .ok_or_else(|| format!("day {} has no simulation to visualize", args.day))?;
+    let part = args.part.ok_or("--visualize needs a --part")?;
+    if !matches!(part, 1 | 2) {
+        return Err(format!("no part {part} to visualize").into());
+    }
+
+    let input = read_input(args.day, args.input.as_deref())?;
+    let output = args.output.as_deref();
+    let open = || -> io::Result<Box<dyn Write>> {
+        Ok(match output {
+            None | Some("-") => Box::new(io::stdout().lock()),
+            Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        })
+    };
+    let mut sink: Box<dyn FrameSink> = match format {
+        "ascii" => Box::new(Ascii(open()?)),
+        "ppm" => Box::new(Ppm::new(open()?, args.scale)),
+        "png" => Box::new(Png::new(
+            output.ok_or("PNG frames need an --output path to be numbered after")?,
+            args.scale,
+        )),
+        "gif" => Box::new(Gif::new(open()?, args.scale, 5)),
+        _ => return Err(format!("unknown visualization format {format:?}").into()),
+    };
+    let mut recorder = Recorder::new(sink.as_mut(), args.every);
+    simulation.run(&input, part, &mut recorder)?;
+    recorder.finish()?;
+    Ok(())
+}
+
+fn run(args: RunArgs) -> Result<(), Box<dyn Error>> {
+    if let Some(format) = &args.visualize {
+        return watch(&args, format);
+    }
+    let selected: Vec<_> = SOLUTIONS
+        .iter()
+        .filter(|s| s.day == args.day)
+        .filter(|s| args.part.is_none_or(|part| s.part == part))
+        .filter(|s| s.variant == args.variant.as_deref())
+        .collect();
+    if selected.is_empty() {
+        return Err(format!(
+            "no solution registered for day {}{}{}",
+            args.day,
+            args.part.map(|p| format!(" part {p}")).unwrap_or_default(),
+            args.variant
+                .as_deref()
+                .map(|v| format!(" ({v})"))
+                .unwrap_or_default(),
+        )
+        .into());
+    }
+
+    let input = read_input(args.day, args.input.as_deref())?;
+    for solution in selected {
+        let start = Instant::now();
+        let runner = solution.generate(&input)?;
+        let generated = Instant::now();
+        let answer = runner.try_run()?;
+        let finished = Instant::now();
+        println!(
+            "{}: {answer}\n\tgenerator: {:?},\n\trunner: {:?}\n",
+            label(solution),
+            generated - start,
+            finished - generated
+        );
+    }
+    Ok(())
+}
+
+fn verify(args: VerifyArgs) -> Result<(), Box<dyn Error>> {
+    let answers = Answers::load(&args.answers)
+        .map_err(|error| format!("couldn't load {}: {error}", args.answers))?;
+    let solutions = SOLUTIONS
+        .iter()
+        .filter(|s| args.day.is_none_or(|day| s.day == day));
+    let outcomes: Vec<_> = verify::verify(solutions, &answers).collect();
+    println!("{}", verify::table(&outcomes));
+    let failures = outcomes.iter().filter(|o| o.status.is_failure()).count();
+    if failures > 0 {
+        return Err(format!("{failures} solution(s) didn't match their recorded answer").into());
+    }
+    Ok(())
+}
+
+fn main() {
+    let mut args = env::args().skip(1);
+    let result = match args.next().as_deref() {
+        Some("run") => parse_run_args(args).map_err(Into::into).and_then(run),
+        Some("verify") => parse_verify_args(args).map_err(Into::into).and_then(verify),
+        Some("help" | "--help" | "-h") => {
+            println!("{USAGE}");
+            return;
+        }
+        _ => {
+            eprintln!("{USAGE}");
+            process::exit(2);
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("error: {error}");
+        process::exit(1);
+    }
+}
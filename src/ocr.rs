@@ -0,0 +1,115 @@
+//! Reads the blocky capital letters that some puzzles draw their answers in.
+//!
+//! Each letter is 4 pixels wide and 6 tall, with a blank column between letters. Only the
+//! letters that have been seen in puzzle answers are known.
+
+use crate::grid::Grid;
+
+pub const GLYPH_WIDTH: usize = 4;
+pub const GLYPH_HEIGHT: usize = 6;
+
+const LETTERS: &str = "ABCEFGHIJKLOPRSUZ";
+
+// The letters above, in order
+const FONT: [&str; GLYPH_HEIGHT] = [
+    ".##..###...##..####.####..##..#..#..###...##.#..#.#.....##..###..###...###.#..#.####",
+    "#..#.#..#.#..#.#....#....#..#.#..#...#.....#.#.#..#....#..#.#..#.#..#.#....#..#....#",
+    "#..#.###..#....###..###..#....####...#.....#.##...#....#..#.#..#.#..#.#....#..#...#.",
+    "####.#..#.#....#....#....#.##.#..#...#.....#.#.#..#....#..#.###..###...##..#..#..#..",
+    "#..#.#..#.#..#.#....#....#..#.#..#...#..#..#.#.#..#....#..#.#....#.#.....#.#..#.#...",
+    "#..#.###...##..####.#.....###.#..#..###..##..#..#.####..##..#....#..#.###...##..####",
+];
+
+/// The pixels of the letter starting at column `x`, as bits
+fn glyph(image: &Grid<bool>, x: usize) -> u32 {
+    (0..GLYPH_HEIGHT)
+        .flat_map(|y| (x..x + GLYPH_WIDTH).map(move |x| (x, y)))
+        .fold(0, |bits, position| {
+            bits << 1 | image.get(position).copied().unwrap_or(false) as u32
+        })
+}
+
+fn font() -> Grid<bool> {
+    Grid::from_vec(
+        FONT[0].len(),
+        FONT.iter()
+            .flat_map(|row| row.bytes())
+            .map(|b| b == b'#')
+            .collect(),
+    )
+}
+
+/// Reads a line of letters, or `None` if the image isn't one or has a letter that isn't
+/// known. The blank column after the last letter may be left out.
+pub fn read(image: &Grid<bool>) -> Option<String> {
+    let spacing = GLYPH_WIDTH + 1;
+    if image.height() != GLYPH_HEIGHT || image.width() == 0 || (image.width() + 1) % spacing > 1 {
+        return None;
+    }
+    let font = font();
+    let known: Vec<_> = LETTERS
+        .chars()
+        .enumerate()
+        .map(|(i, letter)| (glyph(&font, i * spacing), letter))
+        .collect();
+    (0..image.width().div_ceil(spacing))
+        .map(|i| {
+            let bits = glyph(image, i * spacing);
+            known
+                .iter()
+                .find(|&&(glyph, _)| glyph == bits)
+                .map(|&(_, letter)| letter)
+        })
+        .collect()
+}
+
+/// Draws a line of letters, or `None` if there's a letter that isn't known. There's a blank
+/// column after every letter, the way the puzzles draw them.
+pub fn draw(text: &str) -> Option<Grid<bool>> {
+    let spacing = GLYPH_WIDTH + 1;
+    let font = font();
+    let mut image = Grid::new(text.chars().count() * spacing, GLYPH_HEIGHT, false);
+    for (i, letter) in text.chars().enumerate() {
+        let index = LETTERS.find(letter)?;
+        for y in 0..GLYPH_HEIGHT {
+            for x in 0..GLYPH_WIDTH {
+                image[(i * spacing + x, y)] = font[(index * spacing + x, y)];
+            }
+        }
+    }
+    Some(image)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_font() {
+        assert_eq!(read(&font()).as_deref(), Some(LETTERS));
+        assert_eq!(read(&draw(LETTERS).unwrap()).as_deref(), Some(LETTERS));
+        assert_eq!(draw("a"), None);
+    }
+
+    #[test]
+    fn unknown() {
+        let image = |rows: [&str; GLYPH_HEIGHT]| {
+            Grid::from_vec(
+                rows[0].len(),
+                rows.concat().bytes().map(|b| b == b'#').collect(),
+            )
+        };
+        let hi = [
+            "#..#..###.",
+            "#..#...#..",
+            "####...#..",
+            "#..#...#..",
+            "#..#...#..",
+            "#..#..###.",
+        ];
+        assert_eq!(read(&image(hi)).as_deref(), Some("HI"));
+        let blot = hi.map(|row| &row[..4]).map(|row| row.replace('.', "#"));
+        assert_eq!(read(&image(blot.each_ref().map(String::as_str))), None);
+        assert_eq!(read(&image(hi.map(|row| &row[..7]))), None);
+    }
+}
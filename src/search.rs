@@ -0,0 +1,290 @@
+//! Searches over implicit graphs, for the pathfinding days.
+//!
+//! Graphs are never built up front: each search starts from a single state and asks a
+//! successor function where it can go next. States must be hashable so that they're only
+//! explored once.
+
+use std::{
+    cmp::Reverse,
+    collections::{hash_map::Entry, BinaryHeap, VecDeque},
+    hash::Hash,
+};
+
+use fxhash::FxHashMap;
+use num::Zero;
+
+/// A path found by a search, from the start to the goal inclusive.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<S, C> {
+    pub cost: C,
+    pub states: Vec<S>,
+}
+
+/// Every state seen so far, along with the state it was first reached from.
+struct Explored<S> {
+    states: Vec<S>,
+    parents: Vec<usize>,
+    indices: FxHashMap<S, usize>,
+}
+
+impl<S: Clone + Eq + Hash> Explored<S> {
+    fn new(start: S) -> Self {
+        let mut indices = FxHashMap::default();
+        indices.insert(start.clone(), 0);
+        Explored {
+            states: vec![start],
+            parents: vec![0],
+            indices,
+        }
+    }
+
+    /// Returns the index of a state, and whether it's new.
+    fn insert(&mut self, state: S, parent: usize) -> (usize, bool) {
+        if let Some(&index) = self.indices.get(&state) {
+            return (index, false);
+        }
+        let index = self.states.len();
+        self.indices.insert(state.clone(), index);
+        self.states.push(state);
+        self.parents.push(parent);
+        (index, true)
+    }
+
+    fn path(&self, mut index: usize) -> Vec<S> {
+        let mut path = vec![self.states[index].clone()];
+        while index != 0 {
+            index = self.parents[index];
+            path.push(self.states[index].clone());
+        }
+        path.reverse();
+        path
+    }
+}
+
+/// Finds a path with the fewest steps to any goal state.
+pub fn bfs<S, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, usize>>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut explored = Explored::new(start);
+    let mut queue = VecDeque::from([(0, 0)]);
+    while let Some((index, steps)) = queue.pop_front() {
+        if goal(&explored.states[index]) {
+            return Some(Path {
+                cost: steps,
+                states: explored.path(index),
+            });
+        }
+        for next in successors(&explored.states[index]) {
+            if let (next, true) = explored.insert(next, index) {
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    None
+}
+
+/// The number of steps to every state reachable from the start.
+pub fn bfs_distances<S, I>(start: S, mut successors: impl FnMut(&S) -> I) -> FxHashMap<S, usize>
+where
+    S: Clone + Eq + Hash,
+    I: IntoIterator<Item = S>,
+{
+    let mut distances = FxHashMap::default();
+    distances.insert(start.clone(), 0);
+    let mut queue = VecDeque::from([(start, 0)]);
+    while let Some((state, steps)) = queue.pop_front() {
+        for next in successors(&state) {
+            if !distances.contains_key(&next) {
+                distances.insert(next.clone(), steps + 1);
+                queue.push_back((next, steps + 1));
+            }
+        }
+    }
+    distances
+}
+
+/// Finds a cheapest path to any goal state, where each successor comes with the cost of
+/// stepping to it.
+pub fn dijkstra<S, C, I>(
+    start: S,
+    successors: impl FnMut(&S) -> I,
+    goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    astar(start, successors, |_| C::zero(), goal)
+}
+
+/// Like [`dijkstra`], guided by a heuristic. The heuristic must never overestimate the
+/// remaining cost to a goal, or the path found may not be the cheapest.
+pub fn astar<S, C, I>(
+    start: S,
+    mut successors: impl FnMut(&S) -> I,
+    mut heuristic: impl FnMut(&S) -> C,
+    mut goal: impl FnMut(&S) -> bool,
+) -> Option<Path<S, C>>
+where
+    S: Clone + Eq + Hash,
+    C: Copy + Ord + Zero,
+    I: IntoIterator<Item = (S, C)>,
+{
+    let mut heap = BinaryHeap::new();
+    heap.push(Reverse((heuristic(&start), C::zero(), 0)));
+    let mut explored = Explored::new(start);
+    let mut costs = vec![C::zero()];
+    while let Some(Reverse((_, cost, index))) = heap.pop() {
+        // a cheaper way here was found after this one was queued
+        if cost > costs[index] {
+            continue;
+        }
+        if goal(&explored.states[index]) {
+            return Some(Path {
+                cost,
+                states: explored.path(index),
+            });
+        }
+        for (next, step) in successors(&explored.states[index]) {
+            let next_cost = cost + step;
+            let next = match explored.insert(next, index) {
+                (next, true) => {
+                    costs.push(next_cost);
+                    next
+                }
+                (next, false) if next_cost < costs[next] => {
+                    costs[next] = next_cost;
+                    explored.parents[next] = index;
+                    next
+                }
+                _ => continue,
+            };
+            let estimate = next_cost + heuristic(&explored.states[next]);
+            heap.push(Reverse((estimate, next_cost, next)));
+        }
+    }
+    None
+}
+
+/// Tries every order of visiting up to 64 targets, and keeps the most valuable way to
+/// visit each subset of them. Subsets are bitmasks of target indices, and the path of
+/// each is the order its targets were visited in.
+///
+/// `visit(state, value, target)` is the state and value after visiting the target next,
+/// or `None` if it can't be visited from there.
+pub fn best_subsets<S, V>(
+    targets: usize,
+    start: S,
+    value: V,
+    mut visit: impl FnMut(&S, &V, usize) -> Option<(S, V)>,
+) -> FxHashMap<u64, Path<usize, V>>
+where
+    V: Clone + Ord,
+{
+    assert!(targets <= 64, "at most 64 targets fit in a bitmask");
+    let mut best = FxHashMap::default();
+    explore_subsets(
+        targets,
+        &start,
+        value,
+        0,
+        &mut vec![],
+        &mut visit,
+        &mut best,
+    );
+    best
+}
+
+fn explore_subsets<S, V: Clone + Ord>(
+    targets: usize,
+    state: &S,
+    value: V,
+    visited: u64,
+    order: &mut Vec<usize>,
+    visit: &mut impl FnMut(&S, &V, usize) -> Option<(S, V)>,
+    best: &mut FxHashMap<u64, Path<usize, V>>,
+) {
+    for target in (0..targets).filter(|&target| visited & 1 << target == 0) {
+        if let Some((next, next_value)) = visit(state, &value, target) {
+            order.push(target);
+            let visited = visited | 1 << target;
+            explore_subsets(targets, &next, next_value, visited, order, visit, best);
+            order.pop();
+        }
+    }
+    match best.entry(visited) {
+        Entry::Occupied(entry) if entry.get().cost >= value => (),
+        entry => {
+            entry.insert_entry(Path {
+                cost: value,
+                states: order.clone(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a line of states where each can step one or two ahead, but two steps cost three
+    fn line(&state: &u32) -> [(u32, u32); 2] {
+        [(state + 1, 1), (state + 2, 3)]
+    }
+
+    #[test]
+    fn breadth_first() {
+        let path = bfs(0, |&state| [state + 1, state + 2], |&state| state == 5).unwrap();
+        assert_eq!(path.cost, 3);
+        assert_eq!(path.states.len(), 4);
+        assert_eq!(path.states.first(), Some(&0));
+        assert_eq!(path.states.last(), Some(&5));
+
+        assert_eq!(
+            bfs(
+                0,
+                |&state: &u32| (state < 8).then_some(state + 2),
+                |&state| state == 5
+            ),
+            None
+        );
+
+        let distances = bfs_distances(0, |&state: &u32| (state < 4).then_some(state + 2));
+        assert_eq!(distances.len(), 3);
+        assert_eq!(distances[&4], 2);
+    }
+
+    #[test]
+    fn cheapest() {
+        let path = dijkstra(0, line, |&state| state == 4).unwrap();
+        assert_eq!(path.cost, 4);
+        assert_eq!(path.states, vec![0, 1, 2, 3, 4]);
+
+        let guided = astar(
+            0,
+            line,
+            |&state| 4u32.saturating_sub(state),
+            |&state| state == 4,
+        );
+        assert_eq!(guided, Some(path));
+    }
+
+    #[test]
+    fn subsets() {
+        // visiting target `t` is worth `t + 1`, but only in increasing order
+        let best = best_subsets(3, None, 0, |&last: &Option<usize>, &value, target| {
+            (last < Some(target)).then_some((Some(target), value + target + 1))
+        });
+        assert_eq!(best.len(), 8);
+        assert_eq!(best[&0b000].cost, 0);
+        assert_eq!(best[&0b101].cost, 4);
+        assert_eq!(best[&0b111].states, vec![0, 1, 2]);
+    }
+}
@@ -0,0 +1,134 @@
+//! A table of every registered `#[aoc]` solution, so that they can be run
+//! without going through cargo-aoc.
+
+use std::error::Error;
+
+use aoc_runner::{ArcStr, Runner};
+
+use crate::*;
+
+type Build = fn(ArcStr) -> Result<Box<dyn Runner>, Box<dyn Error>>;
+
+#[derive(Clone, Copy)]
+pub struct Solution {
+    pub day: u32,
+    pub part: u32,
+    pub variant: Option<&'static str>,
+    build: Build,
+}
+
+impl Solution {
+    /// Runs the generator for this solution, returning a runner ready to be executed.
+    pub fn generate(&self, input: &str) -> Result<Box<dyn Runner>, Box<dyn Error>> {
+        (self.build)(ArcStr::from(input))
+    }
+
+    /// Whether this solution shows how an answer was reached, rather than giving one.
+    pub fn explains(&self) -> bool {
+        self.variant == Some("explain")
+    }
+}
+
+// The factory functions are generated by `aoc_lib!`, one per day/part/variant
+macro_rules! solutions {
+    ($($day:literal, $part:literal $(, $variant:ident)? => $build:ident;)*) => {
+        pub static SOLUTIONS: &[Solution] = &[$(
+            Solution {
+                day: $day,
+                part: $part,
+                variant: solutions!(@variant $($variant)?),
+                build: Factory::$build,
+            },
+        )*];
+    };
+    (@variant) => { None };
+    (@variant $variant:ident) => { Some(stringify!($variant)) };
+}
+
+solutions! {
+    1, 1 => day1_part1;
+    1, 2 => day1_part2;
+    2, 1 => day2_part1;
+    2, 2 => day2_part2;
+    3, 1 => day3_part1;
+    3, 2 => day3_part2;
+    4, 1 => day4_part1;
+    4, 2 => day4_part2;
+    5, 1 => day5_part1;
+    5, 2 => day5_part2;
+    6, 1 => day6_part1;
+    6, 1, rolling => day6_part1_rolling;
+    6, 2 => day6_part2;
+    6, 2, rolling => day6_part2_rolling;
+    7, 1 => day7_part1;
+    7, 2 => day7_part2;
+    8, 1 => day8_part1;
+    8, 2 => day8_part2;
+    9, 1 => day9_part1;
+    9, 2 => day9_part2;
+    10, 1 => day10_part1;
+    10, 1, explain => day10_part1_explain;
+    10, 2 => day10_part2;
+    10, 2, compiled => day10_part2_compiled;
+    11, 1 => day11_part1;
+    11, 1, exact => day11_part1_exact;
+    11, 1, explain => day11_part1_explain;
+    11, 2 => day11_part2;
+    11, 2, explain => day11_part2_explain;
+    11, 2, orbits => day11_part2_orbits;
+    11, 2, exact => day11_part2_exact;
+    12, 1 => day12_part1;
+    12, 1, astar => day12_part1_astar;
+    12, 1, effort => day12_part1_effort;
+    12, 1, explain => day12_part1_explain;
+    12, 2 => day12_part2;
+    12, 2, nearest => day12_part2_nearest;
+    12, 2, explain => day12_part2_explain;
+    13, 1 => day13_part1;
+    13, 1, explain => day13_part1_explain;
+    13, 2 => day13_part2;
+    14, 1 => day14_part1;
+    14, 1, fast => day14_part1_fast;
+    14, 2 => day14_part2;
+    14, 2, fast => day14_part2_fast;
+    15, 1 => day15_part1;
+    15, 2 => day15_part2;
+    15, 2, rotated => day15_part2_rotated;
+    16, 1 => day16_part1;
+    16, 1, explain => day16_part1_explain;
+    16, 2 => day16_part2;
+    16, 2, explain => day16_part2_explain;
+    17, 1 => day17_part1;
+    17, 2 => day17_part2;
+    18, 1 => day18_part1;
+    18, 2 => day18_part2;
+    19, 1 => day19_part1;
+    19, 1, explain => day19_part1_explain;
+    19, 2 => day19_part2;
+    19, 2, explain => day19_part2_explain;
+    20, 1 => day20_part1;
+    20, 2 => day20_part2;
+    21, 1 => day21_part1;
+    21, 2 => day21_part2;
+    22, 1 => day22_part1;
+    22, 2 => day22_part2;
+    23, 1 => day23_part1;
+    23, 2 => day23_part2;
+    24, 1 => day24_part1;
+    24, 1, explain => day24_part1_explain;
+    24, 2 => day24_part2;
+    24, 2, explain => day24_part2_explain;
+    25, 1 => day25_part1;
+}
+
+/// Finds the solution for a given day and part. A `None` variant selects the default solution.
+pub fn find(day: u32, part: u32, variant: Option<&str>) -> Option<&'static Solution> {
+    SOLUTIONS
+        .iter()
+        .find(|s| s.day == day && s.part == part && s.variant == variant)
+}
+
+/// Where the full puzzle input for a day is kept, relative to the crate root.
+pub fn input_path(day: u32) -> String {
+    format!("input/2022/day{day}.txt")
+}
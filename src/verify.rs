@@ -0,0 +1,272 @@
+//! Checks every registered solution against the answers recorded in `answers.toml`,
+//! so that refactors can't silently change results.
+//!
+//! The manifest has one table per day, keyed by part and optionally by variant:
+//!
+//! ```toml
+//! [day6]
+//! part1 = 1480
+//! part1_rolling = 1480
+//! ```
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    fmt::{self, Write},
+    fs, io,
+    time::{Duration, Instant},
+};
+
+use crate::solutions::{input_path, Solution};
+
+pub const ANSWERS_PATH: &str = "answers.toml";
+
+/// The accepted answers, keyed by day, part and variant.
+#[derive(Debug, Default)]
+pub struct Answers(HashMap<(u32, u32, Option<String>), String>);
+
+impl Answers {
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    pub fn parse(manifest: &str) -> Result<Self, Box<dyn Error>> {
+        let tables: toml::value::Table = toml::from_str(manifest)?;
+        let mut answers = HashMap::new();
+        for (day_key, parts) in tables {
+            let day = day_key
+                .strip_prefix("day")
+                .and_then(|day| day.parse().ok())
+                .ok_or_else(|| format!("expected a `day<N>` table, found `{day_key}`"))?;
+            let parts = parts
+                .as_table()
+                .ok_or_else(|| format!("`{day_key}` should be a table"))?;
+            for (part_key, answer) in parts {
+                let (part, variant) = Self::parse_part_key(part_key).ok_or_else(|| {
+                    format!(
+                        "expected a `part<N>[_<variant>]` key in `{day_key}`, found `{part_key}`"
+                    )
+                })?;
+                let answer = match answer {
+                    toml::Value::String(s) => s.clone(),
+                    toml::Value::Integer(n) => n.to_string(),
+                    _ => {
+                        return Err(format!(
+                            "`{day_key}.{part_key}` should be a string or an integer"
+                        )
+                        .into())
+                    }
+                };
+                answers.insert((day, part, variant), answer);
+            }
+        }
+        Ok(Answers(answers))
+    }
+
+    /// Splits a key like `part2_rolling` into its part and variant. The part number ends at
+    /// the first `_`, and everything after it is the variant, underscores included.
+    fn parse_part_key(key: &str) -> Option<(u32, Option<String>)> {
+        let key = key.strip_prefix("part")?;
+        let digits = key.find(|c: char| !c.is_ascii_digit()).unwrap_or(key.len());
+        let part = key[..digits].parse().ok()?;
+        match &key[digits..] {
+            "" => Some((part, None)),
+            rest => {
+                let variant = rest.strip_prefix('_')?;
+                let is_identifier = !variant.is_empty()
+                    && variant
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || c == '_');
+                is_identifier.then(|| (part, Some(variant.to_string())))
+            }
+        }
+    }
+
+    pub fn get(&self, solution: &Solution) -> Option<&str> {
+        self.0
+            .get(&(
+                solution.day,
+                solution.part,
+                solution.variant.map(str::to_string),
+            ))
+            .map(String::as_str)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    Correct,
+    Mismatch { expected: String, found: String },
+    MissingInput,
+    MissingAnswer { found: String },
+    Failed(String),
+}
+
+impl Status {
+    /// Whether this outcome should fail verification. Missing inputs and answers are
+    /// only reported, since not every checkout has every input.
+    pub fn is_failure(&self) -> bool {
+        matches!(self, Status::Mismatch { .. } | Status::Failed(_))
+    }
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Correct => "ok",
+            Status::Mismatch { .. } => "MISMATCH",
+            Status::MissingInput => "no input",
+            Status::MissingAnswer { .. } => "no answer",
+            Status::Failed(_) => "ERROR",
+        })
+    }
+}
+
+pub struct Outcome {
+    pub solution: &'static Solution,
+    pub status: Status,
+    pub elapsed: Duration,
+}
+
+fn check(solution: &Solution, answers: &Answers) -> Status {
+    let input = match fs::read_to_string(input_path(solution.day)) {
+        Ok(input) => input,
+        Err(error) if error.kind() == io::ErrorKind::NotFound => return Status::MissingInput,
+        Err(error) => return Status::Failed(error.to_string()),
+    };
+    let found = match solution
+        .generate(&input)
+        .and_then(|runner| runner.try_run())
+    {
+        Ok(found) => found.to_string(),
+        Err(error) => return Status::Failed(error.to_string()),
+    };
+    match answers.get(solution) {
+        Some(expected) if expected == found => Status::Correct,
+        Some(expected) => Status::Mismatch {
+            expected: expected.to_string(),
+            found,
+        },
+        None => Status::MissingAnswer { found },
+    }
+}
+
+/// Runs each solution on its full input, in order. Explanations have no answer to check,
+/// so they're skipped.
+pub fn verify<'a, I>(solutions: I, answers: &'a Answers) -> impl Iterator<Item = Outcome> + 'a
+where
+    I: IntoIterator<Item = &'static Solution>,
+    I::IntoIter: 'a,
+{
+    solutions
+        .into_iter()
+        .filter(|solution| !solution.explains())
+        .map(move |solution| {
+            let start = Instant::now();
+            let status = check(solution, answers);
+            Outcome {
+                solution,
+                status,
+                elapsed: start.elapsed(),
+            }
+        })
+}
+
+/// Lays out the outcomes as a table, followed by a summary line.
+pub fn table(outcomes: &[Outcome]) -> String {
+    let header = [
+        "day", "part", "variant", "status", "expected", "found", "time",
+    ]
+    .map(String::from);
+    let rows: Vec<[String; 7]> = outcomes
+        .iter()
+        .map(|outcome| {
+            let (expected, found) = match &outcome.status {
+                Status::Mismatch { expected, found } => (expected.clone(), found.clone()),
+                Status::MissingAnswer { found } => (String::new(), found.clone()),
+                Status::Failed(error) => (String::new(), error.clone()),
+                Status::Correct | Status::MissingInput => (String::new(), String::new()),
+            };
+            [
+                outcome.solution.day.to_string(),
+                outcome.solution.part.to_string(),
+                outcome.solution.variant.unwrap_or("").to_string(),
+                outcome.status.to_string(),
+                expected,
+                found,
+                format!("{:.2?}", outcome.elapsed),
+            ]
+        })
+        .collect();
+
+    let mut widths = header.clone().map(|cell| cell.chars().count());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect::<Vec<_>>()
+            .join("  ");
+        writeln!(table, "{}", line.trim_end()).unwrap();
+    }
+
+    let count = |f: fn(&Status) -> bool| outcomes.iter().filter(|o| f(&o.status)).count();
+    write!(
+        table,
+        "{} ok, {} mismatched, {} failed, {} without input, {} without answer",
+        count(|s| matches!(s, Status::Correct)),
+        count(|s| matches!(s, Status::Mismatch { .. })),
+        count(|s| matches!(s, Status::Failed(_))),
+        count(|s| matches!(s, Status::MissingInput)),
+        count(|s| matches!(s, Status::MissingAnswer { .. })),
+    )
+    .unwrap();
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::find;
+
+    #[test]
+    fn parse_manifest() {
+        let answers = Answers::parse(
+            r#"
+            [day5]
+            part1 = "CMZ"
+            [day6]
+            part1 = 7
+            part1_rolling = 7
+            "#,
+        )
+        .unwrap();
+        assert_eq!(answers.get(find(5, 1, None).unwrap()), Some("CMZ"));
+        assert_eq!(answers.get(find(6, 1, None).unwrap()), Some("7"));
+        assert_eq!(answers.get(find(6, 1, Some("rolling")).unwrap()), Some("7"));
+        assert_eq!(answers.get(find(6, 2, None).unwrap()), None);
+    }
+
+    #[test]
+    fn reject_unknown_keys() {
+        assert!(Answers::parse("[day1]\nanswer = 1").is_err());
+        assert!(Answers::parse("[first]\npart1 = 1").is_err());
+        assert!(Answers::parse("[day1]\npart1_ = 1").is_err());
+        assert!(Answers::parse("[day1]\npart1rolling = 1").is_err());
+        assert!(Answers::parse("[day1]\npart_1 = 1").is_err());
+    }
+
+    #[test]
+    fn variants_with_underscores() {
+        let answers = Answers::parse("[day6]\npart2_rolling_window = 19").unwrap();
+        let key = (6, 2, Some("rolling_window".to_string()));
+        assert_eq!(answers.0.get(&key).map(String::as_str), Some("19"));
+    }
+}
@@ -0,0 +1,377 @@
+//! Frames drawn by the simulation days, and the formats they can be written out in.
+//!
+//! A frame is a grid of characters, drawn the way the puzzle text draws them. The image
+//! formats color each character through [`PALETTE`] and scale it up to a square of pixels.
+
+use std::{
+    error::Error,
+    fs::File,
+    io::{self, BufWriter, Write},
+    iter,
+    path::{Path, PathBuf},
+};
+
+use crate::{error::ParseError, grid::Grid, year_2022};
+
+pub type Frame = Grid<u8>;
+
+/// Somewhere for frames to go, one at a time.
+pub trait FrameSink {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()>;
+
+    /// Called once the simulation is over, to flush anything left over.
+    fn finish(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Hands a simulation's frames to a sink. Frames are only drawn when they'll be kept, so
+/// simulations can record unconditionally.
+pub struct Recorder<'a> {
+    sink: Option<&'a mut dyn FrameSink>,
+    every: usize,
+    steps: usize,
+    error: Option<io::Error>,
+}
+
+impl<'a> Recorder<'a> {
+    /// Keeps one frame out of every `every`, starting with the first.
+    pub fn new(sink: &'a mut dyn FrameSink, every: usize) -> Self {
+        Recorder {
+            sink: Some(sink),
+            every: every.max(1),
+            steps: 0,
+            error: None,
+        }
+    }
+
+    /// A recorder that never draws anything, for when nobody's watching.
+    pub fn off() -> Self {
+        Recorder {
+            sink: None,
+            every: 1,
+            steps: 0,
+            error: None,
+        }
+    }
+
+    pub fn record(&mut self, draw: impl FnOnce() -> Frame) {
+        let step = self.steps;
+        self.steps += 1;
+        if self.error.is_some() || !step.is_multiple_of(self.every) {
+            return;
+        }
+        if let Some(sink) = &mut self.sink {
+            // simulations carry on regardless, the error is reported by `finish`
+            self.error = sink.frame(&draw()).err();
+        }
+    }
+
+    pub fn finish(self) -> io::Result<()> {
+        match (self.error, self.sink) {
+            (Some(error), _) => Err(error),
+            (None, Some(sink)) => sink.finish(),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// The color of each character, by palette index. Anything else is drawn like `#`.
+pub const PALETTE: [(u8, [u8; 3]); 15] = [
+    (b'.', [16, 16, 32]),
+    (b' ', [16, 16, 32]),
+    (b'#', [200, 200, 200]),
+    (b'|', [110, 110, 110]),
+    (b'-', [110, 110, 110]),
+    (b'+', [110, 110, 110]),
+    (b'o', [230, 190, 90]),
+    (b'E', [230, 60, 60]),
+    (b'>', [120, 180, 250]),
+    (b'<', [120, 180, 250]),
+    (b'^', [120, 180, 250]),
+    (b'v', [120, 180, 250]),
+    (b'2', [170, 210, 250]),
+    (b'3', [220, 240, 255]),
+    (b'4', [255, 255, 255]),
+];
+
+fn palette_index(c: u8) -> u8 {
+    PALETTE.iter().position(|&(d, _)| c == d).unwrap_or(2) as u8
+}
+
+/// The palette index of every pixel, row by row, with each cell blown up to a square.
+fn indexed_pixels(frame: &Frame, scale: usize) -> (usize, usize, Vec<u8>) {
+    let (width, height) = (frame.width() * scale, frame.height() * scale);
+    let mut pixels = Vec::with_capacity(width * height);
+    for row in frame.rows() {
+        let scaled: Vec<_> = row
+            .iter()
+            .flat_map(|&c| iter::repeat_n(palette_index(c), scale))
+            .collect();
+        for _ in 0..scale {
+            pixels.extend_from_slice(&scaled);
+        }
+    }
+    (width, height, pixels)
+}
+
+fn rgb_pixels(frame: &Frame, scale: usize) -> (usize, usize, Vec<u8>) {
+    let (width, height, pixels) = indexed_pixels(frame, scale);
+    let rgb = pixels
+        .into_iter()
+        .flat_map(|i| PALETTE[i as usize].1)
+        .collect();
+    (width, height, rgb)
+}
+
+/// Frames as text, separated by blank lines.
+pub struct Ascii<W: Write>(pub W);
+
+impl<W: Write> FrameSink for Ascii<W> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        write!(self.0, "{frame}\n\n")
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.0.flush()
+    }
+}
+
+/// Frames as binary PPM images, one after another in the same stream.
+pub struct Ppm<W: Write> {
+    out: W,
+    scale: usize,
+}
+
+impl<W: Write> Ppm<W> {
+    pub fn new(out: W, scale: usize) -> Self {
+        Ppm { out, scale }
+    }
+}
+
+impl<W: Write> FrameSink for Ppm<W> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let (width, height, rgb) = rgb_pixels(frame, self.scale);
+        write!(self.out, "P6\n{width} {height}\n255\n")?;
+        self.out.write_all(&rgb)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+/// Frames as PNG images, numbered after the given path: `sand.png` becomes
+/// `sand-00000.png`, `sand-00001.png` and so on.
+pub struct Png {
+    path: PathBuf,
+    scale: usize,
+    frames: usize,
+}
+
+impl Png {
+    pub fn new(path: impl AsRef<Path>, scale: usize) -> Self {
+        Png {
+            path: path.as_ref().to_path_buf(),
+            scale,
+            frames: 0,
+        }
+    }
+
+    fn numbered(&self) -> PathBuf {
+        let stem = self.path.file_stem().unwrap_or_default().to_string_lossy();
+        self.path
+            .with_file_name(format!("{stem}-{:05}.png", self.frames))
+    }
+}
+
+impl FrameSink for Png {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let (width, height, rgb) = rgb_pixels(frame, self.scale);
+        let file = BufWriter::new(File::create(self.numbered())?);
+        self.frames += 1;
+        let mut encoder = png::Encoder::new(file, width as u32, height as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&rgb))
+            .map_err(io::Error::other)
+    }
+}
+
+/// Frames as an animated GIF, looping forever. Every frame must be the same size.
+pub struct Gif<W: Write> {
+    // handed over to the encoder once the size is known, and back when it's done
+    out: Option<W>,
+    encoder: Option<(gif::Encoder<W>, (u16, u16))>,
+    scale: usize,
+    /// Between frames, in hundredths of a second
+    delay: u16,
+}
+
+impl<W: Write> Gif<W> {
+    pub fn new(out: W, scale: usize, delay: u16) -> Self {
+        Gif {
+            out: Some(out),
+            encoder: None,
+            scale,
+            delay,
+        }
+    }
+}
+
+fn gif_error(error: gif::EncodingError) -> io::Error {
+    match error {
+        gif::EncodingError::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+impl<W: Write> FrameSink for Gif<W> {
+    fn frame(&mut self, frame: &Frame) -> io::Result<()> {
+        let (width, height, pixels) = indexed_pixels(frame, self.scale);
+        let (width, height) = match (u16::try_from(width), u16::try_from(height)) {
+            (Ok(width), Ok(height)) => (width, height),
+            _ => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "frame too large for a GIF, try a smaller scale",
+                ))
+            }
+        };
+        let encoder = match (&mut self.encoder, self.out.take()) {
+            (Some((encoder, size)), _) if *size == (width, height) => encoder,
+            (Some(_), _) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    "every frame of a GIF must be the same size",
+                ))
+            }
+            (None, Some(out)) => {
+                let palette: Vec<u8> = PALETTE.iter().flat_map(|&(_, rgb)| rgb).collect();
+                let mut encoder =
+                    gif::Encoder::new(out, width, height, &palette).map_err(gif_error)?;
+                encoder
+                    .set_repeat(gif::Repeat::Infinite)
+                    .map_err(gif_error)?;
+                &mut self.encoder.insert((encoder, (width, height))).0
+            }
+            (None, None) => unreachable!("the output is only taken by the encoder"),
+        };
+        let mut frame = gif::Frame::from_indexed_pixels(width, height, pixels, None);
+        frame.delay = self.delay;
+        encoder.write_frame(&frame).map_err(gif_error)
+    }
+
+    fn finish(&mut self) -> io::Result<()> {
+        if let Some((encoder, _)) = self.encoder.take() {
+            self.out = Some(encoder.into_inner()?);
+        }
+        match &mut self.out {
+            Some(out) => out.flush(),
+            None => Ok(()),
+        }
+    }
+}
+
+type Visualize = fn(&str, u32, &mut Recorder) -> Result<(), ParseError>;
+
+/// A day whose simulation can be watched.
+pub struct Simulation {
+    pub day: u32,
+    visualize: Visualize,
+}
+
+impl Simulation {
+    /// Parses the input and runs a part of the simulation, recording as it goes.
+    pub fn run(
+        &self,
+        input: &str,
+        part: u32,
+        recorder: &mut Recorder,
+    ) -> Result<(), Box<dyn Error>> {
+        Ok((self.visualize)(input, part, recorder)?)
+    }
+}
+
+pub static SIMULATIONS: &[Simulation] = &[
+    Simulation {
+        day: 14,
+        visualize: year_2022::day_14::visualize,
+    },
+    Simulation {
+        day: 17,
+        visualize: year_2022::day_17::visualize,
+    },
+    Simulation {
+        day: 23,
+        visualize: year_2022::day_23::visualize,
+    },
+    Simulation {
+        day: 24,
+        visualize: year_2022::day_24::visualize,
+    },
+];
+
+pub fn find(day: u32) -> Option<&'static Simulation> {
+    SIMULATIONS.iter().find(|s| s.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frame() -> Frame {
+        let mut frame = Grid::new(3, 2, b'.');
+        frame[(1, 0)] = b'#';
+        frame[(2, 1)] = b'o';
+        frame
+    }
+
+    #[test]
+    fn recorder() {
+        let mut sink = Ascii(Vec::new());
+        let mut recorder = Recorder::new(&mut sink, 2);
+        let mut drawn = 0;
+        for _ in 0..5 {
+            recorder.record(|| {
+                drawn += 1;
+                frame()
+            });
+        }
+        recorder.finish().unwrap();
+        assert_eq!(drawn, 3);
+        assert_eq!(String::from_utf8(sink.0).unwrap(), ".#.\n..o\n\n".repeat(3));
+
+        let mut off = Recorder::off();
+        off.record(|| unreachable!());
+        off.finish().unwrap();
+    }
+
+    #[test]
+    fn ppm() {
+        let mut sink = Ppm::new(Vec::new(), 2);
+        sink.frame(&frame()).unwrap();
+        let header = b"P6\n6 4\n255\n";
+        assert_eq!(sink.out.len(), header.len() + 6 * 4 * 3);
+        assert!(sink.out.starts_with(header));
+        // each cell is two pixels wide
+        let pixels = &sink.out[header.len()..];
+        assert_eq!(pixels[..3], PALETTE[0].1);
+        assert_eq!(pixels[3..6], PALETTE[0].1);
+        assert_eq!(pixels[6..9], PALETTE[2].1);
+    }
+
+    #[test]
+    fn gif() {
+        let mut sink = Gif::new(Vec::new(), 1, 10);
+        sink.frame(&frame()).unwrap();
+        sink.frame(&frame()).unwrap();
+        assert!(sink.frame(&Grid::new(1, 1, b'.')).is_err());
+        sink.finish().unwrap();
+        let out = sink.out.unwrap();
+        assert!(out.starts_with(b"GIF89a"));
+        assert_eq!(out.last(), Some(&b';'));
+    }
+}
@@ -1,13 +1,15 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 #[aoc_generator(day1)]
-fn generator(input: &str) -> Vec<Vec<usize>> {
+fn generator(input: &str) -> Result<Vec<Vec<usize>>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(1, input);
     input
         .split("\n\n")
-        .map(|chunk| {
-            chunk
-                .split('\n')
-                .map(|line| line.parse().unwrap())
-                .collect()
-        })
+        .map(|chunk| chunk.split('\n').map(|line| ctx.number(line)).collect())
         .collect()
 }
 
@@ -27,3 +29,22 @@ fn entry_part_2(input: &[Vec<usize>]) -> usize {
     sums.select_nth_unstable_by(K, |l, r| r.cmp(l));
     sums.iter().take(K).sum()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day1.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(entry_part_1(&input), 24000);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(entry_part_2(&input), 45000);
+    }
+}
@@ -1,61 +1,510 @@
-#[aoc_generator(day10)]
-fn generator(input: &str) -> Vec<i32> {
-    input
-        .lines()
-        .map(|line| {
-            if line == "noop" {
-                0
-            } else {
-                line[5..].parse::<i32>().unwrap()
+//! The handheld device's CPU and CRT, along with an assembler for writing programs for
+//! them and a compiler that works out a program drawing a given picture.
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::{self, Write},
+};
+
+use crate::{
+    error::{ParseContext, ParseError},
+    grid::Grid,
+    input::normalize,
+    ocr,
+};
+
+/// One of the CPU's registers, `x` being the one the puzzle is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Register(usize);
+
+pub const REGISTERS: &str = "xyzw";
+pub const X: Register = Register(0);
+
+impl fmt::Display for Register {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", REGISTERS.as_bytes()[self.0] as char)
+    }
+}
+
+/// Jumps are relative to the jumping instruction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instruction {
+    Noop,
+    Add(Register, i32),
+    Jmp(isize),
+    Jnz(Register, isize),
+}
+
+impl fmt::Display for Instruction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Instruction::Noop => write!(f, "noop"),
+            Instruction::Add(register, value) => write!(f, "add{register} {value}"),
+            Instruction::Jmp(offset) => write!(f, "jmp {offset}"),
+            Instruction::Jnz(register, offset) => write!(f, "jnz {register} {offset}"),
+        }
+    }
+}
+
+/// How many cycles each kind of instruction takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Costs {
+    pub noop: usize,
+    pub add: usize,
+    pub jump: usize,
+}
+
+impl Default for Costs {
+    fn default() -> Self {
+        Costs {
+            noop: 1,
+            add: 2,
+            jump: 1,
+        }
+    }
+}
+
+impl Costs {
+    pub fn of(&self, instruction: Instruction) -> usize {
+        let cycles = match instruction {
+            Instruction::Noop => self.noop,
+            Instruction::Add(..) => self.add,
+            Instruction::Jmp(_) | Instruction::Jnz(..) => self.jump,
+        };
+        // nothing happens in no time at all
+        cycles.max(1)
+    }
+}
+
+/// Turns source code into a program. On top of the instructions themselves, the source
+/// can have `; comments` and `label:`s, which jumps can use instead of offsets.
+pub fn assemble(ctx: &ParseContext, source: &str) -> Result<Vec<Instruction>, ParseError> {
+    // each line is `[label:] [instruction] [; comment]`, where everything is optional
+    let mut labels = HashMap::new();
+    let mut lines = vec![];
+    for line in source.lines() {
+        let code = line.split_once(';').map_or(line, |(code, _)| code).trim();
+        let code = match code.split_once(':') {
+            Some((label, rest)) => {
+                let label = label.trim();
+                if label.is_empty()
+                    || !label
+                        .bytes()
+                        .all(|b| b.is_ascii_alphanumeric() || b == b'_')
+                {
+                    return Err(ctx.error(label, "a label made of letters, digits and `_`"));
+                }
+                if labels.insert(label, lines.len()).is_some() {
+                    return Err(ctx.error(label, "a label that isn't already defined"));
+                }
+                rest.trim()
+            }
+            None => code,
+        };
+        if !code.is_empty() {
+            lines.push(code);
+        }
+    }
+
+    let register = |name: &str| match REGISTERS.find(name) {
+        Some(index) if name.len() == 1 => Ok(Register(index)),
+        _ => Err(ctx.error(name, "a register, one of `x`, `y`, `z` or `w`")),
+    };
+    let target = |at: usize, target: &str| match labels.get(target) {
+        Some(&line) => Ok(line as isize - at as isize),
+        None if target.starts_with(|c: char| c == '-' || c.is_ascii_digit()) => ctx.number(target),
+        None => Err(ctx.error(target, "an offset or a label defined in the program")),
+    };
+    lines
+        .iter()
+        .enumerate()
+        .map(|(at, line)| {
+            let mut words = line.split_whitespace();
+            let opcode = words.next().unwrap_or(line);
+            let mut operand = |expected: &str| {
+                words
+                    .next()
+                    .ok_or_else(|| ctx.error_after(opcode, format!("{expected} after `{opcode}`")))
+            };
+            let instruction = match opcode {
+                "noop" => Instruction::Noop,
+                "jmp" => Instruction::Jmp(target(at, operand("an offset or a label")?)?),
+                "jnz" => {
+                    let register = register(operand("a register")?)?;
+                    Instruction::Jnz(register, target(at, operand("an offset or a label")?)?)
+                }
+                _ => match opcode.strip_prefix("add") {
+                    Some(name) if !name.is_empty() => {
+                        Instruction::Add(register(name)?, ctx.number(operand("a number")?)?)
+                    }
+                    _ => {
+                        return Err(
+                            ctx.error(opcode, "one of `noop`, `add<register>`, `jmp` or `jnz`")
+                        )
+                    }
+                },
+            };
+            match words.next() {
+                Some(extra) => Err(ctx.error(extra, "the end of the instruction")),
+                None => Ok(instruction),
             }
         })
         .collect()
 }
 
-#[aoc(day10, part1)]
-fn selected_signals(input: &[i32]) -> i32 {
-    let mut it = input
+/// The program the way [`assemble`] reads it back, one instruction per line
+pub fn listing(program: &[Instruction]) -> String {
+    program
         .iter()
-        .copied()
-        .flat_map(|i| if i == 0 { [i32::MAX, 0] } else { [i, 0] })
-        .filter(|&i| i != i32::MAX)
-        .scan(1, |x, i| {
-            let old = *x;
-            *x += i;
-            Some(old)
-        })
-        .enumerate()
-        .map(|(cycle, x)| (cycle + 2, x))
-        .map(|(cycle, x)| cycle as i32 * x);
+        .map(|instruction| format!("{instruction}\n"))
+        .collect()
+}
 
-    let initial = it.nth(18).unwrap();
-    (0..5).fold(initial, |acc, _| acc + it.nth(39).unwrap())
+#[aoc_generator(day10)]
+fn generator(input: &str) -> Result<Vec<Instruction>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(10, input);
+    assemble(&ctx, input)
 }
 
-#[aoc(day10, part2)]
-fn crt_rasterize(input: &[i32]) -> &'static str {
-    let mut grid = [b'.'; 40 * 6];
-    input
-        .iter()
-        .copied()
-        .flat_map(|i| if i == 0 { [i32::MAX, 0] } else { [i, 0] })
-        .filter(|&i| i != i32::MAX)
-        .scan(1, |x, i| {
-            let old = *x;
-            *x += i;
-            Some(old)
-        })
-        .enumerate()
-        .map(|(cycle, x)| (cycle + 1, x))
-        .for_each(|(cycle, x)| {
-            let offset = cycle as i32 % 40;
-            if (offset - x) == (offset - x).signum() {
-                grid[cycle] = b'#';
+pub type Registers = [i32; REGISTERS.len()];
+
+/// What the CPU is up to during a cycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Tick {
+    /// Counting from 1, like the puzzle does
+    pub cycle: usize,
+    /// The registers during the cycle, before the instruction finishes
+    pub registers: Registers,
+    pub instruction: Instruction,
+}
+
+impl Tick {
+    pub fn x(&self) -> i32 {
+        self.registers[X.0]
+    }
+}
+
+/// Runs a program one cycle at a time, as an iterator of [`Tick`]s. It stops once the
+/// program counter leaves the program, which a program with jumps might never do.
+#[derive(Debug, Clone)]
+pub struct Cpu<'a> {
+    program: &'a [Instruction],
+    costs: Costs,
+    pc: usize,
+    registers: Registers,
+    cycle: usize,
+    /// Cycles spent on the current instruction so far
+    busy: usize,
+    breakpoints: BTreeSet<usize>,
+}
+
+impl<'a> Cpu<'a> {
+    pub fn new(program: &'a [Instruction]) -> Self {
+        let mut registers = Registers::default();
+        registers[X.0] = 1;
+        Cpu {
+            program,
+            costs: Costs::default(),
+            pc: 0,
+            registers,
+            cycle: 0,
+            busy: 0,
+            breakpoints: BTreeSet::new(),
+        }
+    }
+
+    pub fn with_costs(mut self, costs: Costs) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    pub fn with_breakpoints(mut self, cycles: impl IntoIterator<Item = usize>) -> Self {
+        self.breakpoints.extend(cycles);
+        self
+    }
+
+    /// Runs until the next cycle with a breakpoint, or the end of the program. Once every
+    /// breakpoint has been passed there's nothing left to stop at, so it gives up straight
+    /// away rather than running what could be an endless loop.
+    pub fn resume(&mut self) -> Option<Tick> {
+        self.breakpoints.range(self.cycle + 1..).next()?;
+        loop {
+            let tick = self.next()?;
+            if self.breakpoints.contains(&tick.cycle) {
+                return Some(tick);
+            }
+        }
+    }
+
+    /// A line for each cycle left to run, up to `cycles` of them, with breakpoints marked by
+    /// their signal strength. Registers other than `x` are only shown once they've been used.
+    pub fn trace(self, cycles: usize) -> String {
+        let breakpoints = self.breakpoints.clone();
+        let mut trace = String::new();
+        for tick in self.take(cycles) {
+            write!(trace, "cycle {:>3}:", tick.cycle).unwrap();
+            for (i, &value) in tick.registers.iter().enumerate() {
+                if i == X.0 || value != 0 {
+                    write!(trace, " {} = {value:>3},", Register(i)).unwrap();
+                }
+            }
+            write!(trace, " {}", tick.instruction).unwrap();
+            if breakpoints.contains(&tick.cycle) {
+                write!(trace, " <- signal strength {}", signal_strength(tick)).unwrap();
+            }
+            trace.push('\n');
+        }
+        trace
+    }
+}
+
+impl Iterator for Cpu<'_> {
+    type Item = Tick;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let &instruction = self.program.get(self.pc)?;
+        self.cycle += 1;
+        let tick = Tick {
+            cycle: self.cycle,
+            registers: self.registers,
+            instruction,
+        };
+        self.busy += 1;
+        if self.busy == self.costs.of(instruction) {
+            self.busy = 0;
+            let offset = match instruction {
+                Instruction::Noop => 1,
+                Instruction::Add(register, value) => {
+                    self.registers[register.0] += value;
+                    1
+                }
+                Instruction::Jmp(offset) => offset,
+                Instruction::Jnz(register, offset) if self.registers[register.0] != 0 => offset,
+                Instruction::Jnz(..) => 1,
             };
-        });
+            // jumping off either end halts
+            self.pc = self.pc.checked_add_signed(offset).unwrap_or(usize::MAX);
+        }
+        Some(tick)
+    }
+}
+
+pub fn signal_strength(tick: Tick) -> i32 {
+    tick.cycle as i32 * tick.x()
+}
+
+const SELECTED: [usize; 6] = [20, 60, 100, 140, 180, 220];
+
+/// How many cycles it takes to draw the whole screen
+const CYCLES: usize = 40 * 6;
+
+#[aoc(day10, part1)]
+fn selected_signals(input: &[Instruction]) -> i32 {
+    let mut cpu = Cpu::new(input).with_breakpoints(SELECTED);
+    std::iter::from_fn(|| cpu.resume())
+        .map(signal_strength)
+        .sum()
+}
+
+#[aoc(day10, part1, explain)]
+fn trace_signals(input: &[Instruction]) -> String {
+    Cpu::new(input).with_breakpoints(SELECTED).trace(CYCLES)
+}
+
+#[aoc(day10, part2)]
+fn crt_rasterize(input: &[Instruction]) -> String {
+    let screen = crt_screen(input);
+    // a picture is still worth looking at, even if it's not letters
+    ocr::read(&screen).unwrap_or_else(|| draw(&screen))
+}
+
+/// Compiles the picture a program draws back into a program of its own, and reads what
+/// that one draws instead. It should always come to the same thing.
+#[aoc(day10, part2, compiled)]
+fn crt_round_trip(input: &[Instruction]) -> String {
+    // `x` only ever changes at the end of an instruction, after at least two cycles
+    let program = compile_crt(&crt_screen(input)).expect("every CPU can draw this");
+    let program = generator(&listing(&program)).expect("listings assemble");
+    crt_rasterize(&program)
+}
+
+/// What a program draws on the CRT, where a pixel is lit if the sprite covers it
+pub fn crt_screen(input: &[Instruction]) -> Grid<bool> {
+    let mut screen = Grid::new(40, 6, false);
+    for tick in Cpu::new(input).take(CYCLES) {
+        let (x, y) = ((tick.cycle - 1) % 40, (tick.cycle - 1) / 40);
+        // the sprite is three pixels wide
+        if (x as i32 - tick.x()).abs() <= 1 {
+            screen[(x, y)] = true;
+        }
+    }
+    screen
+}
+
+fn draw(screen: &Grid<bool>) -> String {
+    screen.map(|&lit| if lit { b'#' } else { b'.' }).to_string()
+}
+
+/// Writes an `addx`/`noop` program that draws a picture on the CRT, if there is one. Not
+/// every picture can be drawn, since `x` only changes after the two cycles of an `addx`.
+pub fn compile_crt(screen: &Grid<bool>) -> Option<Vec<Instruction>> {
+    let cycles = screen.width() * screen.height();
+    // any sprite position further out than this is as good as hidden
+    let positions = -2..=screen.width() as i32 + 1;
+    let index = |x: i32| (x - positions.start()) as usize;
+    let drawable = |cycle: usize, x: i32| {
+        let column = cycle % screen.width();
+        cycle >= cycles
+            || screen[(column, cycle / screen.width())] == ((column as i32 - x).abs() <= 1)
+    };
+
+    // whether the rest of the picture can be drawn from a cycle with the sprite somewhere
+    let mut finishable = vec![vec![true; positions.clone().count()]; cycles + 2];
+    for cycle in (0..cycles).rev() {
+        let any_next = finishable[cycle + 2].contains(&true);
+        for x in positions.clone() {
+            let noop = finishable[cycle + 1][index(x)];
+            let addx = drawable(cycle + 1, x) && any_next;
+            finishable[cycle][index(x)] = drawable(cycle, x) && (noop || addx);
+        }
+    }
+
+    let (mut cycle, mut x) = (0, 1);
+    let mut program = vec![];
+    while cycle < cycles {
+        if !finishable[cycle][index(x)] {
+            return None;
+        }
+        if finishable[cycle + 1][index(x)] {
+            program.push(Instruction::Noop);
+            cycle += 1;
+        } else {
+            let next = positions
+                .clone()
+                .find(|&next| finishable[cycle + 2][index(next)])?;
+            program.push(Instruction::Add(X, next - x));
+            (cycle, x) = (cycle + 2, next);
+        }
+    }
+    Some(program)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day10.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(selected_signals(&input), 13140);
+    }
+
+    #[test]
+    fn cpu() {
+        let program = generator("noop\naddx 3\naddx -5").unwrap();
+        let ticks: Vec<_> = Cpu::new(&program)
+            .map(|tick| (tick.cycle, tick.x()))
+            .collect();
+        assert_eq!(ticks, [(1, 1), (2, 1), (3, 1), (4, 4), (5, 4)]);
+        let mut cpu = Cpu::new(&program).with_breakpoints([3, 5, 9]);
+        assert_eq!(
+            cpu.resume(),
+            Some(Tick {
+                cycle: 3,
+                registers: [1, 0, 0, 0],
+                instruction: Instruction::Add(X, 3)
+            })
+        );
+        assert_eq!(cpu.resume().map(|tick| tick.x()), Some(4));
+        assert_eq!(cpu.resume(), None);
+        assert_eq!(Cpu::new(&program).last().map(|tick| tick.cycle), Some(5));
+        assert!(generator("addx 1\nmulx 2").is_err());
+
+        // spinning forever only runs as far as the last breakpoint
+        let spin = generator("addx 1\nhere: jmp here").unwrap();
+        let mut cpu = Cpu::new(&spin).with_breakpoints([3, 5]);
+        assert_eq!(cpu.resume().map(|tick| tick.x()), Some(2));
+        assert_eq!(cpu.resume().map(|tick| tick.cycle), Some(5));
+        assert_eq!(cpu.resume(), None);
+        let trace = Cpu::new(&spin).trace(10);
+        assert_eq!(trace.lines().count(), 10);
+        assert!(trace.ends_with("cycle  10: x =   2, jmp 0\n"), "{trace}");
+        assert!(generator("noop 1").is_err());
+    }
+
+    #[test]
+    fn assembler() {
+        let source = "\
+            ; count y down from 3, adding 2 to x each time
+                addy 3
+            loop:
+                addx 2
+                addy -1 ; almost done
+                jnz y loop
+            done: noop
+        ";
+        let ctx = ParseContext::new(10, source);
+        let program = assemble(&ctx, source).unwrap();
+        assert_eq!(
+            listing(&program),
+            "addy 3\naddx 2\naddy -1\njnz y -2\nnoop\n"
+        );
+        assert_eq!(generator(&listing(&program)).unwrap(), program);
+
+        let last = Cpu::new(&program).last().unwrap();
+        assert_eq!(last.registers, [7, 0, 0, 0]);
+        assert_eq!(last.cycle, 2 + 3 * (2 + 2 + 1) + 1);
+        let costs = Costs {
+            noop: 1,
+            add: 1,
+            jump: 3,
+        };
+        let last = Cpu::new(&program).with_costs(costs).last().unwrap();
+        assert_eq!(last.cycle, 1 + 3 * (1 + 1 + 3) + 1);
+
+        for source in [
+            "jmp nowhere",
+            "a:\na: noop",
+            "addq 1",
+            "jnz 1 2",
+            "noop noop",
+        ] {
+            assert!(assemble(&ParseContext::new(10, source), source).is_err());
+        }
+    }
+
+    #[test]
+    fn compiler() {
+        let screen = ocr::draw("BZPAJELK").unwrap();
+        let program = compile_crt(&screen).unwrap();
+        assert_eq!(crt_screen(&program), screen);
+        assert_eq!(crt_rasterize(&program), "BZPAJELK");
+
+        let example = crt_screen(&generator(EXAMPLE).unwrap());
+        assert_eq!(crt_screen(&compile_crt(&example).unwrap()), example);
 
-    grid.chunks(40)
-        .for_each(|chunk| println!("{}", std::str::from_utf8(chunk).unwrap()));
+        // `x` can't move between the first two cycles
+        let mut checkers = Grid::new(40, 6, false);
+        checkers[(0, 0)] = true;
+        checkers[(2, 0)] = true;
+        assert_eq!(compile_crt(&checkers), None);
+    }
 
-    ""
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(
+            crt_rasterize(&input),
+            "\
+##..##..##..##..##..##..##..##..##..##..
+###...###...###...###...###...###...###.
+####....####....####....####....####....
+#####.....#####.....#####.....#####.....
+######......######......######......####
+#######.......#######.......#######....."
+        );
+    }
 }
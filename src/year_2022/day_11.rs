@@ -1,25 +1,107 @@
-use num::integer::lcm;
+//! Monkeys playing keep away, and a [`History`] of where each item goes that can skip
+//! straight to any round.
 
-#[derive(Debug, Clone, Copy)]
-enum Operation {
-    Add(u64),
-    Mul(u64),
-    Square,
+use std::{
+    collections::hash_map::Entry,
+    fmt::{self, Display, Formatter},
+    ops::{Add, Mul, Sub},
+};
+
+use fxhash::FxHashMap;
+
+use nom::{
+    branch::alt,
+    bytes::complete::tag,
+    character::complete::{char, one_of, space0, u64},
+    combinator::{map, value},
+    multi::fold_many0,
+    sequence::{delimited, pair, preceded},
+};
+use num::{
+    integer::{lcm, Integer},
+    BigInt, One, Signed, Zero,
+};
+
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
+/// The right hand side of `new = ...`
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    Old,
+    Constant(u64),
+    Add(Box<Expr>, Box<Expr>),
+    Sub(Box<Expr>, Box<Expr>),
+    Mul(Box<Expr>, Box<Expr>),
 }
 
-impl Operation {
-    fn apply(&self, x: u64) -> u64 {
+impl Expr {
+    /// Works for any kind of integer. Reducing the result modulo anything gives the same
+    /// answer as reducing `old` first, so worry levels can be kept small.
+    fn eval<T>(&self, old: &T) -> T
+    where
+        T: Clone + From<u64> + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+    {
         match self {
-            Operation::Add(y) => x + y,
-            Operation::Mul(y) => x * y,
-            Operation::Square => x * x,
+            Expr::Old => old.clone(),
+            Expr::Constant(n) => T::from(*n),
+            Expr::Add(a, b) => a.eval(old) + b.eval(old),
+            Expr::Sub(a, b) => a.eval(old) - b.eval(old),
+            Expr::Mul(a, b) => a.eval(old) * b.eval(old),
         }
     }
+
+    /// Like [`Expr::eval`] modulo `m`, reducing as it goes so that `old * old * old` can't
+    /// overflow.
+    fn eval_mod(&self, old: u64, m: u64) -> u64 {
+        let (old, m) = (old as u128, m as u128);
+        let eval = |expr: &Expr| expr.eval_mod(old as u64, m as u64) as u128;
+        let result = match self {
+            Expr::Old => old % m,
+            Expr::Constant(n) => *n as u128 % m,
+            Expr::Add(a, b) => (eval(a) + eval(b)) % m,
+            Expr::Sub(a, b) => (eval(a) + m - eval(b)) % m,
+            Expr::Mul(a, b) => eval(a) * eval(b) % m,
+        };
+        result as u64
+    }
 }
 
-#[derive(Debug, Clone, Copy)]
-struct Monkey {
-    op: Operation,
+// Multiplication binds tighter than addition and subtraction, all of them to the left
+fn parse_expr(input: &str) -> nom::IResult<&str, Expr> {
+    let (input, first) = parse_term(input)?;
+    fold_many0(
+        pair(delimited(space0, one_of("+-"), space0), parse_term),
+        move || first.clone(),
+        |a, (op, b)| match op {
+            '+' => Expr::Add(Box::new(a), Box::new(b)),
+            _ => Expr::Sub(Box::new(a), Box::new(b)),
+        },
+    )(input)
+}
+
+fn parse_term(input: &str) -> nom::IResult<&str, Expr> {
+    let (input, first) = parse_atom(input)?;
+    fold_many0(
+        preceded(delimited(space0, char('*'), space0), parse_atom),
+        move || first.clone(),
+        |a, b| Expr::Mul(Box::new(a), Box::new(b)),
+    )(input)
+}
+
+fn parse_atom(input: &str) -> nom::IResult<&str, Expr> {
+    alt((
+        value(Expr::Old, tag("old")),
+        map(u64, Expr::Constant),
+        delimited(pair(char('('), space0), parse_expr, pair(space0, char(')'))),
+    ))(input)
+}
+
+#[derive(Debug, Clone)]
+pub struct Monkey {
+    op: Expr,
     test: u64,
     success: usize,
     failure: usize,
@@ -27,29 +109,81 @@ struct Monkey {
 
 impl Monkey {
     fn handle(&self, x: u64, relief: u64, factor: u64) -> Item {
-        let post_op = self.op.apply(x) / relief % factor;
-        if post_op % self.test == 0 {
-            Item {
-                value: post_op,
-                index: self.success,
-            }
-        } else {
-            Item {
-                value: post_op,
-                index: self.failure,
+        // the worry level after relief, modulo `factor`, only depends on the worry level
+        // before it modulo `relief * factor`
+        let modulus = relief
+            .checked_mul(factor)
+            .expect("the relief times the product of the tests should fit in a `u64`");
+        let post_op = self.op.eval_mod(x, modulus).div_euclid(relief) % factor;
+        self.throw(post_op, post_op.is_multiple_of(self.test))
+    }
+
+    /// Like [`Monkey::handle`], keeping as much of the worry level as `modulus` says can
+    /// still make a difference.
+    fn handle_exact(&self, worry: &Worry, relief: u64, modulus: &BigInt) -> Item<Worry> {
+        let worry = match worry {
+            Worry::Exact(x) => {
+                let mut x = self.op.eval(x).div_floor(&BigInt::from(relief));
+                if x.is_negative() || x >= *modulus {
+                    x = x.mod_floor(modulus);
+                }
+                Worry::Exact(x)
             }
+            Worry::Residues(residues) => Worry::Residues(
+                residues
+                    .iter()
+                    .map(|&(m, x)| (m, self.op.eval_mod(x, m)))
+                    .collect(),
+            ),
+        };
+        let passed = worry.is_multiple_of(self.test);
+        self.throw(worry, passed)
+    }
+
+    fn throw<W>(&self, value: W, passed: bool) -> Item<W> {
+        Item {
+            value,
+            index: if passed { self.success } else { self.failure },
         }
     }
 }
 
+/// An item and the monkey holding it
 #[derive(Debug, Clone, Copy)]
-struct Item {
-    value: u64,
-    index: usize,
+pub struct Item<W = u64> {
+    /// The worry level
+    pub value: W,
+    pub index: usize,
 }
 
+/// A worry level that's never reduced so far that it could change what a test says.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Worry {
+    /// The whole thing, which can be divided by any relief, for as long as all of it can
+    /// matter. Dividing by the relief brings one more digit (in base `relief`) down to where
+    /// the tests see it, so over the next `k` inspections only the remainder modulo
+    /// `relief^k` times the tests' common multiple ever does. It's negative if subtracting
+    /// took it below zero.
+    Exact(BigInt),
+    /// Only the remainder for each test, as `(divisor, remainder)` pairs. That's all the tests
+    /// ever need, but there's no dividing it by anything.
+    Residues(Vec<(u64, u64)>),
+}
+
+impl Worry {
+    fn is_multiple_of(&self, test: u64) -> bool {
+        match self {
+            Worry::Exact(x) => x.mod_floor(&BigInt::from(test)).is_zero(),
+            Worry::Residues(residues) => residues.iter().any(|&(m, x)| m == test && x == 0),
+        }
+    }
+}
+
+/// Reads the monkeys, along with the items they start out holding.
 #[aoc_generator(day11)]
-fn generator(input: &str) -> (Vec<Monkey>, Vec<Item>) {
+pub fn generator(input: &str) -> Result<(Vec<Monkey>, Vec<Item>), ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(11, input);
     let mut monkeys = vec![];
     let mut items = vec![];
     for chunk in input.split("\n\n") {
@@ -60,60 +194,158 @@ fn generator(input: &str) -> (Vec<Monkey>, Vec<Item>) {
         let mut failure = None;
         for line in chunk.lines() {
             if let Some(string) = line.strip_prefix("Monkey ") {
-                index = string
+                let string = string
                     .strip_suffix(':')
-                    .and_then(|s| s.parse::<usize>().ok());
+                    .ok_or_else(|| ctx.error_after(string, "\":\""))?;
+                index = Some(ctx.number::<usize>(string)?);
             } else if let Some(string) = line.strip_prefix("  Starting items: ") {
-                items.extend(
-                    string
-                        .split(", ")
-                        .filter_map(|s| s.parse::<u64>().ok())
-                        .filter_map(|value| index.map(|i| Item { value, index: i })),
-                );
-            } else if line == "  Operation: new = old * old" {
-                op = Some(Operation::Square);
-            } else if let Some(target) = line.strip_prefix("  Operation: new = old * ") {
-                op = target.parse::<u64>().ok().map(Operation::Mul);
-            } else if let Some(target) = line.strip_prefix("  Operation: new = old + ") {
-                op = target.parse::<u64>().ok().map(Operation::Add);
+                let index = index.ok_or_else(|| ctx.error(line, "a `Monkey <n>:` header"))?;
+                for value in string.split(", ") {
+                    items.push(Item {
+                        value: ctx.number(value)?,
+                        index,
+                    });
+                }
+            } else if let Some(expr) = line.strip_prefix("  Operation: new = ") {
+                op = Some(match parse_expr(expr) {
+                    Ok(("", expr)) => expr,
+                    Ok((rest, _)) => return Err(ctx.error(rest, "`+`, `-` or `*`")),
+                    Err(nom::Err::Error(e) | nom::Err::Failure(e)) => {
+                        return Err(ctx.error(e.input, "`old`, a number or `(`"))
+                    }
+                    Err(nom::Err::Incomplete(_)) => {
+                        return Err(ctx.error_after(expr, "an expression"))
+                    }
+                });
             } else if let Some(val) = line.strip_prefix("  Test: divisible by ") {
-                test = val.parse::<u64>().ok();
+                test = Some(ctx.number(val)?);
             } else if let Some(target) = line.strip_prefix("    If true: throw to monkey ") {
-                success = target.parse::<usize>().ok();
+                success = Some(ctx.number(target)?);
             } else if let Some(target) = line.strip_prefix("    If false: throw to monkey ") {
-                failure = target.parse::<usize>().ok();
+                failure = Some(ctx.number(target)?);
+            } else {
+                return Err(ctx.error(line, "a monkey attribute"));
             }
         }
+        let missing = |attribute| ctx.error_after(chunk, format!("the `{attribute}` line"));
         monkeys.push(Monkey {
-            op: op.unwrap(),
-            test: test.unwrap(),
-            success: success.unwrap(),
-            failure: failure.unwrap(),
+            op: op.ok_or_else(|| missing("Operation:"))?,
+            test: test.ok_or_else(|| missing("Test:"))?,
+            success: success.ok_or_else(|| missing("If true:"))?,
+            failure: failure.ok_or_else(|| missing("If false:"))?,
         })
     }
-    (monkeys, items)
+    Ok((monkeys, items))
 }
 
 fn juggle((monkeys, items): &(Vec<Monkey>, Vec<Item>), steps: usize, relief: u64) -> u64 {
-    let mut counters = vec![0; monkeys.len()];
     let factor = monkeys.iter().map(|m| m.test).fold(1, lcm);
-    let mut items = (*items).clone();
-    for _ in 0..steps {
-        for item in items.iter_mut() {
-            let mut initial = *item;
-            let mut new = monkeys[initial.index].handle(initial.value, relief, factor);
-            counters[initial.index] += 1;
-            while new.index >= initial.index {
-                initial = new;
-                new = monkeys[initial.index].handle(initial.value, relief, factor);
-                counters[initial.index] += 1;
+    rounds(monkeys, items.clone(), steps, |_, monkey, &x| {
+        monkey.handle(x, relief, factor)
+    })
+}
+
+/// Like [`juggle`], but right for any relief. Without any, each item only keeps its remainder
+/// for every test. Otherwise worry levels are kept whole until `old * old` makes them too long
+/// for all of it to matter in the rounds that are left. That still leaves a lot of digits when
+/// the relief is small: on the real input 10000 rounds take a few seconds with a relief of 4,
+/// and the better part of a minute with 2.
+fn juggle_exact((monkeys, items): &(Vec<Monkey>, Vec<Item>), steps: usize, relief: u64) -> u64 {
+    if relief == 1 {
+        let items = items
+            .iter()
+            .map(|item| Item {
+                value: Worry::Residues(
+                    monkeys
+                        .iter()
+                        .map(|m| (m.test, item.value % m.test))
+                        .collect(),
+                ),
+                index: item.index,
+            })
+            .collect();
+        let modulus = BigInt::one();
+        return rounds(monkeys, items, steps, |_, monkey, worry| {
+            monkey.handle_exact(worry, relief, &modulus)
+        });
+    }
+
+    let items = items
+        .iter()
+        .map(|item| Item {
+            value: Worry::Exact(item.value.into()),
+            index: item.index,
+        })
+        .collect();
+    // enough digits for the most inspections an item could have left in each round
+    let factor = monkeys.iter().map(|m| m.test).fold(1, lcm);
+    let per_round = BigInt::from(relief).pow(longest_round(monkeys) as u32);
+    let mut modulus = per_round.pow(steps as u32) * factor;
+    let mut current = 0;
+    rounds(monkeys, items, steps, |round, monkey, worry| {
+        while current < round {
+            modulus /= &per_round;
+            current += 1;
+        }
+        monkey.handle_exact(worry, relief, &modulus)
+    })
+}
+
+/// The most times an item can be inspected in one round. Monkeys only ever throw an item on
+/// to one later in the round by throwing it to a higher index.
+fn longest_round(monkeys: &[Monkey]) -> usize {
+    let mut longest = vec![1; monkeys.len()];
+    for (i, monkey) in monkeys.iter().enumerate().rev() {
+        for target in [monkey.success, monkey.failure] {
+            if target > i {
+                longest[i] = longest[i].max(1 + longest[target]);
             }
-            *item = new;
         }
     }
+    longest.into_iter().max().unwrap_or(0)
+}
+
+/// Plays out the rounds and returns the level of monkey business. `handle` is told which
+/// round it is, counting from 0.
+fn rounds<W>(
+    monkeys: &[Monkey],
+    mut items: Vec<Item<W>>,
+    steps: usize,
+    mut handle: impl FnMut(usize, &Monkey, &W) -> Item<W>,
+) -> u64 {
+    let mut counters = vec![0; monkeys.len()];
+    for round in 0..steps {
+        let mut handle = |monkey: &Monkey, worry: &W| handle(round, monkey, worry);
+        for item in items.iter_mut() {
+            play(monkeys, item, &mut handle, |monkey| counters[monkey] += 1);
+        }
+    }
+    business(&counters)
+}
+
+/// Plays out one round for a single item, telling `inspected` about each monkey that gets it.
+fn play<W>(
+    monkeys: &[Monkey],
+    item: &mut Item<W>,
+    handle: &mut impl FnMut(&Monkey, &W) -> Item<W>,
+    mut inspected: impl FnMut(usize),
+) {
+    inspected(item.index);
+    let mut new = handle(&monkeys[item.index], &item.value);
+    // monkeys later in the round get to throw it again
+    while new.index >= item.index {
+        *item = new;
+        inspected(item.index);
+        new = handle(&monkeys[item.index], &item.value);
+    }
+    *item = new;
+}
+
+/// The product of the two largest counts
+fn business(counters: &[u64]) -> u64 {
     let mut first = 0;
     let mut second = 0;
-    for count in counters {
+    for &count in counters {
         if count > first {
             second = first;
             first = count;
@@ -124,6 +356,173 @@ fn juggle((monkeys, items): &(Vec<Monkey>, Vec<Item>), steps: usize, relief: u64
     first * second
 }
 
+/// Where one item went, round by round. Items never affect each other, and with worry
+/// levels reduced there are only so many states an item can be in, so sooner or later it
+/// goes round in circles.
+#[derive(Debug, Clone)]
+struct Orbit {
+    /// The item at the start of each round, up to the first repeat
+    states: Vec<Item>,
+    /// The monkeys that inspected the item in each of those rounds, in order
+    paths: Vec<Vec<usize>>,
+    /// How many times each monkey had inspected the item after each number of rounds
+    totals: Vec<Vec<u64>>,
+    /// The first round of the cycle, which goes on forever after the last recorded round
+    cycle_start: usize,
+}
+
+impl Orbit {
+    fn new(monkeys: &[Monkey], item: Item, relief: u64, factor: u64) -> Self {
+        let mut seen = FxHashMap::default();
+        let mut orbit = Orbit {
+            states: vec![],
+            paths: vec![],
+            totals: vec![vec![0; monkeys.len()]],
+            cycle_start: 0,
+        };
+        let mut item = item;
+        let mut handle = |monkey: &Monkey, &x: &u64| monkey.handle(x, relief, factor);
+        while let Entry::Vacant(entry) = seen.entry((item.value, item.index)) {
+            entry.insert(orbit.states.len());
+            orbit.states.push(item);
+            let mut path = vec![];
+            let mut totals = orbit.totals[orbit.totals.len() - 1].clone();
+            play(monkeys, &mut item, &mut handle, |monkey| {
+                path.push(monkey);
+                totals[monkey] += 1;
+            });
+            orbit.paths.push(path);
+            orbit.totals.push(totals);
+        }
+        orbit.cycle_start = seen[&(item.value, item.index)];
+        orbit
+    }
+
+    fn period(&self) -> usize {
+        self.states.len() - self.cycle_start
+    }
+
+    /// The round that plays out the same way as round `n`, counting from 0
+    fn equivalent(&self, n: usize) -> usize {
+        if n < self.cycle_start {
+            n
+        } else {
+            self.cycle_start + (n - self.cycle_start) % self.period()
+        }
+    }
+
+    /// The item at the start of round `n`, counting from 0
+    fn state(&self, n: usize) -> Item {
+        self.states[self.equivalent(n)]
+    }
+
+    /// The monkeys that inspect the item in round `n`, counting from 0
+    fn path(&self, n: usize) -> &[usize] {
+        &self.paths[self.equivalent(n)]
+    }
+
+    /// How many times each monkey has inspected the item after `n` rounds
+    fn inspections(&self, n: usize) -> Vec<u64> {
+        if n <= self.cycle_start {
+            return self.totals[n].clone();
+        }
+        let cycles = ((n - self.cycle_start) / self.period()) as u64;
+        let (start, end) = (
+            &self.totals[self.cycle_start],
+            &self.totals[self.states.len()],
+        );
+        let rest = &self.totals[self.equivalent(n)];
+        (0..start.len())
+            .map(|i| rest[i] + cycles * (end[i] - start[i]))
+            .collect()
+    }
+}
+
+/// Every item's orbit, for skipping straight to any round.
+#[derive(Debug, Clone)]
+pub struct History {
+    orbits: Vec<Orbit>,
+}
+
+impl History {
+    /// Follows every item until it goes round in circles. Worry levels are kept modulo the
+    /// tests' common multiple, which is only right without relief or for the first few
+    /// rounds, like in the puzzle.
+    pub fn new((monkeys, items): &(Vec<Monkey>, Vec<Item>), relief: u64) -> Self {
+        let factor = monkeys.iter().map(|m| m.test).fold(1, lcm);
+        History {
+            orbits: items
+                .iter()
+                .map(|&item| Orbit::new(monkeys, item, relief, factor))
+                .collect(),
+        }
+    }
+
+    /// How many items each monkey has inspected after `n` rounds
+    pub fn inspections(&self, n: usize) -> Vec<u64> {
+        self.orbits
+            .iter()
+            .map(|orbit| orbit.inspections(n))
+            .reduce(|a, b| a.iter().zip(b).map(|(a, b)| a + b).collect())
+            .unwrap_or_default()
+    }
+
+    /// The level of monkey business after `n` rounds
+    pub fn business(&self, n: usize) -> u64 {
+        business(&self.inspections(n))
+    }
+
+    /// Where every item is at the start of round `n`, counting from 0, in the order they
+    /// were first listed
+    pub fn items(&self, n: usize) -> Vec<Item> {
+        self.orbits.iter().map(|orbit| orbit.state(n)).collect()
+    }
+
+    /// The monkeys that inspect an item, round by round, forever
+    pub fn lineage(&self, item: usize) -> impl Iterator<Item = &[usize]> {
+        let orbit = &self.orbits[item];
+        (0..).map(|n| orbit.path(n))
+    }
+
+    /// The inspections after each of a few rounds, the way the puzzle shows them.
+    pub fn report(&self, rounds: &[usize]) -> Report {
+        Report {
+            rounds: rounds.iter().map(|&n| (n, self.inspections(n))).collect(),
+            periods: self.orbits.iter().map(Orbit::period).collect(),
+        }
+    }
+}
+
+pub struct Report {
+    rounds: Vec<(usize, Vec<u64>)>,
+    periods: Vec<usize>,
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut periods = self.periods.clone();
+        periods.sort_unstable();
+        periods.dedup();
+        let periods: Vec<_> = periods.iter().map(usize::to_string).collect();
+        writeln!(
+            f,
+            "{} items, each repeating every {} rounds",
+            self.periods.len(),
+            periods.join(" or ")
+        )?;
+        for (n, inspections) in &self.rounds {
+            writeln!(f, "\n== After round {n} ==")?;
+            for (monkey, count) in inspections.iter().enumerate() {
+                writeln!(f, "Monkey {monkey} inspected items {count} times.")?;
+            }
+        }
+        if let Some((_, inspections)) = self.rounds.last() {
+            write!(f, "\nmonkey business: {}", business(inspections))?;
+        }
+        Ok(())
+    }
+}
+
 #[aoc(day11, part1)]
 fn annoying(input: &(Vec<Monkey>, Vec<Item>)) -> u64 {
     juggle(input, 20, 3)
@@ -133,3 +532,135 @@ fn annoying(input: &(Vec<Monkey>, Vec<Item>)) -> u64 {
 fn stressful(input: &(Vec<Monkey>, Vec<Item>)) -> u64 {
     juggle(input, 10000, 1)
 }
+
+#[aoc(day11, part1, explain)]
+fn annoying_report(input: &(Vec<Monkey>, Vec<Item>)) -> Report {
+    History::new(input, 3).report(&(1..=20).collect::<Vec<_>>())
+}
+
+#[aoc(day11, part2, explain)]
+fn stressful_report(input: &(Vec<Monkey>, Vec<Item>)) -> Report {
+    let mut rounds = vec![1, 20];
+    rounds.extend((1..=10).map(|k| k * 1000));
+    History::new(input, 1).report(&rounds)
+}
+
+#[aoc(day11, part2, orbits)]
+fn stressful_orbits(input: &(Vec<Monkey>, Vec<Item>)) -> u64 {
+    History::new(input, 1).business(10000)
+}
+
+#[aoc(day11, part1, exact)]
+fn annoying_exact(input: &(Vec<Monkey>, Vec<Item>)) -> u64 {
+    juggle_exact(input, 20, 3)
+}
+
+#[aoc(day11, part2, exact)]
+fn stressful_exact(input: &(Vec<Monkey>, Vec<Item>)) -> u64 {
+    juggle_exact(input, 10000, 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day11.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(annoying(&input), 10605);
+    }
+
+    #[test]
+    fn operations() {
+        let eval = |expr: &str, old: i128| {
+            let (rest, expr) = parse_expr(expr).unwrap();
+            assert_eq!(rest, "");
+            expr.eval(&old)
+        };
+        assert_eq!(eval("old * old", 7), 49);
+        assert_eq!(eval("old + old", 7), 14);
+        assert_eq!(eval("1 + old * 2", 7), 15);
+        assert_eq!(eval("(1 + old) * 2", 7), 16);
+        assert_eq!(eval("old - 3 - 2", 7), 2);
+        assert_eq!(eval("( old-10 )*old", 7), -21);
+
+        let monkey = EXAMPLE.replace("new = old * 19", "new = (old - old) * 19 + old * 19");
+        assert_eq!(annoying(&generator(&monkey).unwrap()), 10605);
+        assert_eq!(parse_expr("old * old * old").unwrap().1.eval_mod(7, 10), 3);
+        assert_eq!(parse_expr("old - 3").unwrap().1.eval_mod(1, 10), 8);
+        let (_, cube) = parse_expr("old * old * old").unwrap();
+        assert_eq!(cube.eval_mod(u64::MAX - 1, u64::MAX), u64::MAX - 1);
+        assert!(generator(&EXAMPLE.replace("old * 19", "old / 19")).is_err());
+        assert!(generator(&EXAMPLE.replace("old * 19", "(old * 19")).is_err());
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(stressful(&input), 2713310158);
+    }
+
+    #[test]
+    fn exact() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(annoying_exact(&input), 10605);
+        assert_eq!(stressful_exact(&input), 2713310158);
+        // the two busiest monkeys settle into inspecting 5 items a round
+        assert_eq!(juggle_exact(&input, 10000, 4), 50000 * 50000);
+        assert_eq!(juggle_exact(&input, 10000, 5), 50000 * 50000);
+
+        // worry levels that go below zero, checked against the real thing for a few rounds
+        let input = generator(&EXAMPLE.replace("new = old + 3", "new = old - 70")).unwrap();
+        for relief in [1, 2, 3, 4] {
+            let (monkeys, items) = &input;
+            let items = items
+                .iter()
+                .map(|item| Item {
+                    value: BigInt::from(item.value),
+                    index: item.index,
+                })
+                .collect();
+            let exact = rounds(monkeys, items, 12, |_, monkey, x| {
+                let x = monkey.op.eval(x).div_floor(&BigInt::from(relief));
+                let passed = x.mod_floor(&BigInt::from(monkey.test)).is_zero();
+                monkey.throw(x, passed)
+            });
+            assert_eq!(juggle_exact(&input, 12, relief), exact, "relief {relief}");
+        }
+    }
+
+    #[test]
+    fn large() {
+        // worry levels up to a product of tests over 2^40, cubed
+        let cubed = EXAMPLE
+            .replace("new = old * old", "new = old * old * old")
+            .replace("divisible by 17", "divisible by 4294967291");
+        let input = generator(&cubed).unwrap();
+        assert_eq!(juggle(&input, 1000, 1), juggle_exact(&input, 1000, 1));
+        // and ones that go below zero
+        let input = generator(&EXAMPLE.replace("new = old + 3", "new = old - 70")).unwrap();
+        assert_eq!(juggle(&input, 1000, 1), juggle_exact(&input, 1000, 1));
+    }
+
+    #[test]
+    fn history() {
+        let input = generator(EXAMPLE).unwrap();
+        let history = History::new(&input, 3);
+        assert_eq!(history.business(20), 10605);
+        // 79 goes from the first monkey to the fourth, and ends up with the second
+        assert_eq!(history.lineage(0).next(), Some(&[0, 3][..]));
+        let item = history.items(1)[0];
+        assert_eq!((item.value, item.index), (167, 1));
+
+        let history = History::new(&input, 1);
+        assert_eq!(history.inspections(1), vec![2, 4, 3, 6]);
+        assert_eq!(history.inspections(20), vec![99, 97, 8, 103]);
+        assert_eq!(history.business(10000), 2713310158);
+        for orbit in &history.orbits {
+            let n = orbit.cycle_start + 3 * orbit.period() + 1;
+            assert_eq!(orbit.path(n), orbit.path(orbit.cycle_start + 1));
+        }
+    }
+}
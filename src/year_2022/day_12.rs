@@ -1,69 +1,245 @@
-use std::collections::VecDeque;
+//! The hill climbing puzzle, and a [`Heightmap`] for finding any other way around the hill.
 
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    error::{ParseContext, ParseError},
+    grid::Grid,
+    input::normalize,
+    search::{self, Path},
+};
+
+/// The hill, with the start and the goal at elevations `a` and `z`
 #[derive(Debug)]
-struct Graph {
-    vertices: usize,
-    start: usize,
-    end: usize,
-    neighbors: Vec<[usize; 4]>,
-    reverse_neighbors: Vec<[usize; 4]>,
-    grid: Vec<u8>,
+pub struct Graph {
+    pub start: (usize, usize),
+    pub end: (usize, usize),
+    pub grid: Grid<u8>,
 }
 
 #[aoc_generator(day12)]
-fn generator(input: &str) -> Graph {
-    let width = input.lines().next().unwrap().len();
-    let height = input.len() / (width + 1); // newline included
-    let vertices = width * height;
-
-    let mut neighbors = vec![[usize::MAX; 4]; vertices];
-    let mut reverse_neighbors = vec![[usize::MAX; 4]; vertices];
-
-    let mut grid: Vec<_> = input.bytes().filter(|&b| b != b'\n').collect();
+pub fn generator(input: &str) -> Result<Graph, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(12, input);
+    let mut grid = Grid::parse(&ctx, input, "an elevation letter", |c| {
+        matches!(c, b'a'..=b'z' | b'S' | b'E').then_some(c)
+    })?;
 
-    let start = (0..input.len()).find(|&i| grid[i] == b'S').unwrap();
-    let end = (0..input.len()).find(|&i| grid[i] == b'E').unwrap();
+    let start = grid
+        .position(|&b| b == b'S')
+        .ok_or_else(|| ctx.error_after(input, "a start square `S`"))?;
+    let end = grid
+        .position(|&b| b == b'E')
+        .ok_or_else(|| ctx.error_after(input, "a goal square `E`"))?;
 
     grid[start] = b'a';
     grid[end] = b'z';
 
-    for y in 0..height {
-        for x in 0..width {
-            let index = y * width + x;
-            let elevation = grid[index];
-            if x > 0 && grid[index - 1] <= elevation + 1 {
-                neighbors[index][0] = index - 1;
-                reverse_neighbors[index - 1][0] = index
-            }
-            if x < width - 1 && grid[index + 1] <= elevation + 1 {
-                neighbors[index][1] = index + 1;
-                reverse_neighbors[index + 1][1] = index
-            }
-            if y > 0 && grid[index - width] <= elevation + 1 {
-                neighbors[index][2] = index - width;
-                reverse_neighbors[index - width][2] = index
-            }
-            if y < height - 1 && grid[index + width] <= elevation + 1 {
-                neighbors[index][3] = index + width;
-                reverse_neighbors[index + width][3] = index
-            }
+    Ok(Graph { start, end, grid })
+}
+
+/// Which steps are allowed between squares.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rules {
+    /// How much higher a square can be than the one before it
+    pub ascent: u8,
+    /// How much lower a square can be than the one before it
+    pub descent: u8,
+    /// Whether diagonal steps are allowed too
+    pub diagonal: bool,
+}
+
+impl Rules {
+    /// One letter up, any number of letters down, and no cutting corners
+    pub const PUZZLE: Rules = Rules {
+        ascent: 1,
+        descent: u8::MAX,
+        diagonal: false,
+    };
+
+    pub fn allows(&self, from: u8, to: u8) -> bool {
+        to <= from.saturating_add(self.ascent) && from <= to.saturating_add(self.descent)
+    }
+
+    /// The rules for walking the same steps backwards.
+    pub fn reversed(self) -> Rules {
+        Rules {
+            ascent: self.descent,
+            descent: self.ascent,
+            ..self
         }
     }
+}
+
+/// What each step costs, for finding the cheapest hike rather than the shortest.
+pub trait CostModel {
+    fn cost(&self, from: u8, to: u8) -> u32;
+
+    /// The least any step can cost, so that estimates of the cost to go never overshoot.
+    fn cheapest_step(&self) -> u32;
+}
 
-    Graph {
-        vertices,
-        start,
-        end,
-        neighbors,
-        reverse_neighbors,
-        grid,
+/// Every step costs the same, which is all the puzzle cares about.
+#[derive(Debug, Clone, Copy)]
+pub struct Steps;
+
+impl CostModel for Steps {
+    fn cost(&self, _: u8, _: u8) -> u32 {
+        1
+    }
+
+    fn cheapest_step(&self) -> u32 {
+        1
     }
 }
 
-#[derive(PartialEq, Eq)]
-struct Node {
-    index: usize,
-    distance: u32,
+/// Walking takes some effort, climbing takes more for every letter gained, and coming down
+/// is hard on the knees.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Effort {
+    pub step: u32,
+    /// For every letter higher
+    pub ascent: u32,
+    /// For every letter lower
+    pub descent: u32,
+}
+
+impl Effort {
+    /// Climbing a letter is as much work as two steps on the flat, and dropping one as much
+    /// as another step.
+    pub const HIKER: Effort = Effort {
+        step: 1,
+        ascent: 2,
+        descent: 1,
+    };
+}
+
+impl CostModel for Effort {
+    fn cost(&self, from: u8, to: u8) -> u32 {
+        let up = to.saturating_sub(from) as u32;
+        let down = from.saturating_sub(to) as u32;
+        self.step + self.ascent * up + self.descent * down
+    }
+
+    fn cheapest_step(&self) -> u32 {
+        self.step
+    }
+}
+
+/// The hill, with a way of getting around it.
+#[derive(Debug, Clone, Copy)]
+pub struct Heightmap<'a> {
+    grid: &'a Grid<u8>,
+    rules: Rules,
+}
+
+impl<'a> Heightmap<'a> {
+    pub fn new(grid: &'a Grid<u8>, rules: Rules) -> Self {
+        Heightmap { grid, rules }
+    }
+
+    /// The squares a single step can reach.
+    pub fn moves(&self, (x, y): (usize, usize)) -> impl Iterator<Item = (usize, usize)> + use<'a> {
+        let Heightmap { grid, rules } = *self;
+        let orthogonal = (!rules.diagonal).then(|| grid.neighbors4((x, y)));
+        let all = rules.diagonal.then(|| grid.neighbors8((x, y)));
+        (orthogonal.into_iter().flatten())
+            .chain(all.into_iter().flatten())
+            .filter(move |&next| rules.allows(grid[(x, y)], grid[next]))
+    }
+
+    /// A shortest path between two squares.
+    pub fn path(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+    ) -> Option<Path<(usize, usize), usize>> {
+        self.path_to(from, |&position| position == to)
+    }
+
+    /// A shortest path to whichever goal is nearest.
+    pub fn path_to(
+        &self,
+        from: (usize, usize),
+        goal: impl FnMut(&(usize, usize)) -> bool,
+    ) -> Option<Path<(usize, usize), usize>> {
+        search::bfs(from, |&position| self.moves(position), goal)
+    }
+
+    /// The fewest steps between two squares.
+    pub fn distance(&self, from: (usize, usize), to: (usize, usize)) -> Option<usize> {
+        self.path(from, to).map(|path| path.cost)
+    }
+
+    /// A cheapest path between two squares, guided by how many steps are left at least.
+    pub fn cheapest(
+        &self,
+        from: (usize, usize),
+        to: (usize, usize),
+        model: &impl CostModel,
+    ) -> Option<Path<(usize, usize), u32>> {
+        let Heightmap { grid, rules } = *self;
+        search::astar(
+            from,
+            |&position| {
+                self.moves(position)
+                    .map(move |next| (next, model.cost(grid[position], grid[next])))
+            },
+            |&(x, y)| {
+                let (dx, dy) = (x.abs_diff(to.0), y.abs_diff(to.1));
+                let steps = if rules.diagonal { dx.max(dy) } else { dx + dy };
+                steps as u32 * model.cheapest_step()
+            },
+            |&position| position == to,
+        )
+    }
+
+    /// A shortest path from any of the sources to any goal.
+    pub fn nearest(
+        &self,
+        sources: impl IntoIterator<Item = (usize, usize)>,
+        mut goal: impl FnMut(&(usize, usize)) -> bool,
+    ) -> Option<Path<(usize, usize), usize>> {
+        let sources: Vec<_> = sources.into_iter().collect();
+        // a made up square one step before every source
+        let path = search::bfs(
+            None,
+            |&position| {
+                let start = position.is_none().then_some(&sources);
+                let next = position.map(|position| self.moves(position));
+                start
+                    .into_iter()
+                    .flatten()
+                    .copied()
+                    .chain(next.into_iter().flatten())
+                    .map(Some)
+            },
+            |position| position.as_ref().is_some_and(&mut goal),
+        )?;
+        Some(Path {
+            cost: path.cost - 1,
+            states: path.states.into_iter().flatten().collect(),
+        })
+    }
+
+    /// The number of steps from a square to every other, if it's reachable at all.
+    pub fn distances(&self, from: (usize, usize)) -> Grid<Option<usize>> {
+        let mut field = self.grid.map(|_| None);
+        for (position, steps) in search::bfs_distances(from, |&position| self.moves(position)) {
+            field[position] = Some(steps);
+        }
+        field
+    }
+
+    /// Every square at most `steps` steps away, including the one started from.
+    pub fn within(&self, from: (usize, usize), steps: usize) -> Vec<(usize, usize)> {
+        let field = self.distances(from);
+        field
+            .iter()
+            .filter(|&(_, distance)| distance.is_some_and(|distance| distance <= steps))
+            .map(|(position, _)| position)
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -73,63 +249,224 @@ enum Part {
 }
 
 // dijkstra's algorithm is over twice as slow with the given input
-fn bfs(graph: &Graph, part: Part) -> Option<u32> {
-    let mut distances = vec![u32::MAX; graph.vertices];
-    let mut heap = VecDeque::new();
+fn bfs(graph: &Graph, part: Part) -> Option<Path<(usize, usize), usize>> {
+    let grid = &graph.grid;
+    match part {
+        Part::One => Heightmap::new(grid, Rules::PUZZLE).path(graph.start, graph.end),
+        // walk the same steps backwards, from the top
+        Part::Two => Heightmap::new(grid, Rules::PUZZLE.reversed())
+            .path_to(graph.end, |&position| grid[position] == b'a')
+            .map(|mut path| {
+                path.states.reverse();
+                path
+            }),
+    }
+}
 
-    let first = match part {
-        Part::One => graph.start,
-        Part::Two => graph.end,
-    };
+/// The squares visited on the way up, from the bottom to `E`
+#[derive(Debug)]
+struct Route {
+    width: usize,
+    height: usize,
+    squares: Vec<(usize, usize)>,
+}
 
-    heap.push_back(Node {
-        index: first,
-        distance: 0,
-    });
-    distances[first] = 0;
-
-    while let Some(Node { index, distance }) = heap.pop_front() {
-        // found
-        match part {
-            Part::One => {
-                if index == graph.end {
-                    return Some(distance);
-                }
-            }
-            Part::Two => {
-                if graph.grid[index] == b'a' {
-                    return Some(distance);
-                }
-            }
+impl Route {
+    fn new(graph: &Graph, part: Part) -> Route {
+        let squares = bfs(graph, part).expect("no way up").states;
+        Route {
+            width: graph.grid.width(),
+            height: graph.grid.height(),
+            squares,
         }
-        // suboptimal
-        if distance > distances[index] {
-            continue;
+    }
+
+    fn steps(&self) -> usize {
+        self.squares.len() - 1
+    }
+}
+
+// Drawn like the puzzle text does: each square points to the next one
+impl fmt::Display for Route {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut map = Grid::new(self.width, self.height, b'.');
+        for pair in self.squares.windows(2) {
+            let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+            map[pair[0]] = match (x1.cmp(&x0), y1.cmp(&y0)) {
+                (Ordering::Greater, _) => b'>',
+                (Ordering::Less, _) => b'<',
+                (_, Ordering::Greater) => b'v',
+                _ => b'^',
+            };
         }
-        let neighbors = match part {
-            Part::One => &graph.neighbors,
-            Part::Two => &graph.reverse_neighbors,
-        };
-        for neighbor in neighbors[index] {
-            // ooh improvement
-            if neighbor != usize::MAX && distance + 1 < distances[neighbor] {
-                heap.push_back(Node {
-                    distance: distance + 1,
-                    index: neighbor,
-                });
-                distances[neighbor] = distance + 1;
-            }
+        if let Some(&end) = self.squares.last() {
+            map[end] = b'E';
         }
+        writeln!(f, "{} steps", self.steps())?;
+        write!(f, "{map}")
     }
-    None // :(
 }
 
 #[aoc(day12, part1)]
-fn climb_up(grid: &Graph) -> u32 {
-    bfs(grid, Part::One).unwrap()
+fn climb_up(grid: &Graph) -> usize {
+    bfs(grid, Part::One).unwrap().cost
+}
+
+#[aoc(day12, part1, astar)]
+fn climb_up_astar(grid: &Graph) -> u32 {
+    let heightmap = Heightmap::new(&grid.grid, Rules::PUZZLE);
+    heightmap
+        .cheapest(grid.start, grid.end, &Steps)
+        .unwrap()
+        .cost
+}
+
+/// The least effort it takes to get to the top, rather than the fewest steps.
+#[aoc(day12, part1, effort)]
+fn climb_up_effort(grid: &Graph) -> u32 {
+    let heightmap = Heightmap::new(&grid.grid, Rules::PUZZLE);
+    heightmap
+        .cheapest(grid.start, grid.end, &Effort::HIKER)
+        .unwrap()
+        .cost
+}
+
+#[aoc(day12, part1, explain)]
+fn explain_climb_up(grid: &Graph) -> Route {
+    Route::new(grid, Part::One)
 }
 
 #[aoc(day12, part2)]
-fn climb_down(grid: &Graph) -> u32 {
-    bfs(grid, Part::Two).unwrap()
+fn climb_down(grid: &Graph) -> usize {
+    bfs(grid, Part::Two).unwrap().cost
+}
+
+/// Searches up from every square at the bottom at once, instead of back down from the top.
+#[aoc(day12, part2, nearest)]
+fn climb_down_nearest(grid: &Graph) -> usize {
+    let bottom = grid.grid.iter().filter(|&(_, &height)| height == b'a');
+    Heightmap::new(&grid.grid, Rules::PUZZLE)
+        .nearest(bottom.map(|(position, _)| position), |&p| p == grid.end)
+        .unwrap()
+        .cost
+}
+
+#[aoc(day12, part2, explain)]
+fn explain_climb_down(grid: &Graph) -> Route {
+    Route::new(grid, Part::Two)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day12.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(climb_up(&input), 31);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(climb_down(&input), 29);
+    }
+
+    #[test]
+    fn route() {
+        let input = generator(EXAMPLE).unwrap();
+        for part in [Part::One, Part::Two] {
+            let route = Route::new(&input, part);
+            assert_eq!(input.grid[route.squares[0]], b'a');
+            assert_eq!(route.squares.last(), Some(&input.end));
+            for pair in route.squares.windows(2) {
+                let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+                assert_eq!(x0.abs_diff(x1) + y0.abs_diff(y1), 1);
+                assert!(input.grid[pair[1]] <= input.grid[pair[0]] + 1);
+            }
+        }
+        assert_eq!(Route::new(&input, Part::One).steps(), 31);
+    }
+
+    #[test]
+    fn heightmap() {
+        let input = generator(EXAMPLE).unwrap();
+        let heightmap = Heightmap::new(&input.grid, Rules::PUZZLE);
+        assert_eq!(heightmap.distance(input.start, input.end), Some(31));
+        // any drop is allowed, so the way back down is straight
+        assert_eq!(heightmap.distance(input.end, input.start), Some(5 + 2));
+        assert_eq!(heightmap.distances(input.start)[input.end], Some(31));
+        // the start, and the squares to the right of and below it
+        assert_eq!(heightmap.within(input.start, 1).len(), 3);
+
+        let backwards = Heightmap::new(&input.grid, Rules::PUZZLE.reversed());
+        assert_eq!(backwards.distance(input.end, input.start), Some(31));
+        let bottom = input.grid.iter().filter(|&(_, &height)| height == b'a');
+        let path = heightmap
+            .nearest(bottom.map(|(position, _)| position), |&p| p == input.end)
+            .unwrap();
+        assert_eq!(path.cost, 29);
+        assert_eq!(input.grid[path.states[0]], b'a');
+        assert_eq!(climb_down_nearest(&input), 29);
+
+        let diagonal = Rules {
+            diagonal: true,
+            ..Rules::PUZZLE
+        };
+        let path = Heightmap::new(&input.grid, diagonal)
+            .path(input.start, input.end)
+            .unwrap();
+        assert!(path.cost < 31);
+        assert!(path
+            .states
+            .windows(2)
+            .any(|pair| pair[0].0 != pair[1].0 && pair[0].1 != pair[1].1));
+
+        let flat = Rules {
+            ascent: 0,
+            descent: 0,
+            diagonal: false,
+        };
+        assert_eq!(
+            Heightmap::new(&input.grid, flat).distance(input.start, input.end),
+            None
+        );
+    }
+
+    #[test]
+    fn cheapest() {
+        let input = generator(EXAMPLE).unwrap();
+        let grid = &input.grid;
+        assert_eq!(climb_up_astar(&input), 31);
+        // the shortest way up never goes down, and no way up can do better
+        assert_eq!(climb_up_effort(&input), 31 + 2 * 25);
+
+        let effort = Effort {
+            step: 1,
+            ascent: 2,
+            descent: 3,
+        };
+        for diagonal in [false, true] {
+            let rules = Rules {
+                diagonal,
+                ..Rules::PUZZLE
+            };
+            let heightmap = Heightmap::new(&input.grid, rules);
+            let path = heightmap.cheapest(input.start, input.end, &effort).unwrap();
+            // every letter on the way up has to be climbed at least once
+            assert!(path.cost >= path.states.len() as u32 - 1 + 2 * 25);
+            let unguided = search::dijkstra(
+                input.start,
+                |&position| {
+                    heightmap
+                        .moves(position)
+                        .map(move |next| (next, effort.cost(grid[position], grid[next])))
+                },
+                |&position| position == input.end,
+            );
+            assert_eq!(unguided.map(|path| path.cost), Some(path.cost));
+        }
+    }
 }
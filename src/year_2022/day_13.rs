@@ -1,14 +1,32 @@
+//! Distress signal packets, which can be parsed, compared, converted to and from JSON, and
+//! shown side by side as a [`Comparison`] that marks where they differ.
+
 use core::slice;
-use std::cmp::Ordering;
+use std::{
+    cmp::Ordering,
+    error::Error,
+    fmt::{self, Display, Formatter, Write},
+    str::FromStr,
+};
 
 use nom::{
-    branch::alt, character::complete::char, combinator::map, multi::separated_list0,
-    number::complete::double, sequence::delimited,
+    branch::alt,
+    character::complete::{char, i128},
+    combinator::{cut, map, opt},
+    multi::many0,
+    sequence::{delimited, pair, preceded},
 };
+use serde_json::Value;
 
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
+/// Either an integer or a list of packets, ordered the way the puzzle compares them.
 #[derive(PartialEq, Eq, Debug, Clone)]
-enum Packet {
-    Int(u32),
+pub enum Packet {
+    Int(i128),
     List(Vec<Packet>),
 }
 
@@ -33,32 +51,240 @@ impl Packet {
     fn wrap(self) -> Self {
         Self::List(vec![self])
     }
+
+    fn parse(ctx: &ParseContext, line: &str) -> Result<Self, ParseError> {
+        match parse_packet(line) {
+            Ok(("", packet)) => Ok(packet),
+            Ok((rest, _)) => Err(ctx.error(rest, "the end of the packet")),
+            Err(nom::Err::Error(e)) => Err(ctx.error(e.input, expected(line, e.input, false))),
+            Err(nom::Err::Failure(e)) => Err(ctx.error(e.input, expected(line, e.input, true))),
+            Err(nom::Err::Incomplete(_)) => Err(ctx.error_after(line, "a packet")),
+        }
+    }
+
+    /// Where the packets first compare differently, as the indices leading down to it.
+    fn divergence(&self, other: &Packet) -> Option<(Vec<usize>, Difference)> {
+        match (self, other) {
+            (Packet::Int(x), Packet::Int(y)) => {
+                (x != y).then(|| (vec![], Difference::Values(*x, *y)))
+            }
+            (Packet::List(xs), Packet::List(ys)) => Packet::diverge(xs, ys),
+            (x, Packet::List(ys)) => Packet::diverge(slice::from_ref(x), ys),
+            (Packet::List(xs), y) => Packet::diverge(xs, slice::from_ref(y)),
+        }
+    }
+
+    fn diverge(xs: &[Packet], ys: &[Packet]) -> Option<(Vec<usize>, Difference)> {
+        for (i, (x, y)) in xs.iter().zip(ys).enumerate() {
+            if let Some((mut path, difference)) = x.divergence(y) {
+                path.insert(0, i);
+                return Some((path, difference));
+            }
+        }
+        let end = xs.len().min(ys.len());
+        match xs.len().cmp(&ys.len()) {
+            Ordering::Less => Some((vec![end], Difference::LeftRanOut)),
+            Ordering::Greater => Some((vec![end], Difference::RightRanOut)),
+            Ordering::Equal => None,
+        }
+    }
+
+    /// Writes the packet out, and returns the column of whatever `path` leads to. Integers
+    /// stand in for the lists they get wrapped in, and a list that ran out is marked at its
+    /// closing bracket.
+    fn render(&self, path: Option<&[usize]>, out: &mut String) -> Option<usize> {
+        let at = out.len();
+        match self {
+            Packet::Int(n) => {
+                write!(out, "{n}").unwrap();
+                path.map(|_| at)
+            }
+            Packet::List(xs) => {
+                out.push('[');
+                let mut marked = None;
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        out.push(',');
+                    }
+                    let inner = path.filter(|path| path.first() == Some(&i));
+                    marked = x.render(inner.map(|path| &path[1..]), out).or(marked);
+                }
+                let close = out.len();
+                out.push(']');
+                match path? {
+                    [] => Some(at),
+                    [i, ..] if *i >= xs.len() => Some(close),
+                    _ => marked,
+                }
+            }
+        }
+    }
+}
+
+/// The first thing that tells two packets apart.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Difference {
+    Values(i128, i128),
+    LeftRanOut,
+    RightRanOut,
+}
+
+impl Display for Packet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Packet::Int(n) => write!(f, "{n}"),
+            Packet::List(xs) => {
+                f.write_char('[')?;
+                for (i, x) in xs.iter().enumerate() {
+                    if i > 0 {
+                        f.write_char(',')?;
+                    }
+                    write!(f, "{x}")?;
+                }
+                f.write_char(']')
+            }
+        }
+    }
+}
+
+impl FromStr for Packet {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Packet::parse(&ParseContext::new(13, s), s)
+    }
+}
+
+impl From<&Packet> for Value {
+    fn from(packet: &Packet) -> Self {
+        match packet {
+            Packet::Int(n) => Value::from(*n),
+            Packet::List(xs) => Value::Array(xs.iter().map(Value::from).collect()),
+        }
+    }
+}
+
+/// Part of a JSON value that can't be made into a packet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotAPacket(pub Value);
+
+impl Display for NotAPacket {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` isn't a packet", self.0)
+    }
+}
+
+impl Error for NotAPacket {}
+
+impl TryFrom<&Value> for Packet {
+    type Error = NotAPacket;
+
+    fn try_from(value: &Value) -> Result<Self, Self::Error> {
+        match value {
+            // numbers keep their digits, so anything but a plain integer is turned away
+            Value::Number(n) => (n.to_string().parse())
+                .ok()
+                .map(Packet::Int)
+                .ok_or_else(|| NotAPacket(value.clone())),
+            Value::Array(xs) => xs
+                .iter()
+                .map(Packet::try_from)
+                .collect::<Result<_, _>>()
+                .map(Packet::List),
+            _ => Err(NotAPacket(value.clone())),
+        }
+    }
+}
+
+/// Two packets one above the other, with where they first differ marked on both.
+#[derive(Debug, Clone)]
+pub struct Comparison {
+    pub left: Packet,
+    pub right: Packet,
+}
+
+impl Display for Comparison {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let Some((path, difference)) = self.left.divergence(&self.right) else {
+            writeln!(f, "{}", self.left)?;
+            writeln!(f, "{}", self.right)?;
+            return write!(f, "the packets are the same");
+        };
+        for packet in [&self.left, &self.right] {
+            let mut line = String::new();
+            let column = packet.render(Some(&path), &mut line).unwrap_or(0);
+            writeln!(f, "{line}")?;
+            writeln!(f, "{:>1$}", "^", column + 1)?;
+        }
+        let (reason, order) = match difference {
+            Difference::Values(x, y) if x < y => (format!("{x} < {y}"), "in"),
+            Difference::Values(x, y) => (format!("{x} > {y}"), "not in"),
+            Difference::LeftRanOut => ("the left side ran out first".into(), "in"),
+            Difference::RightRanOut => ("the right side ran out first".into(), "not in"),
+        };
+        write!(f, "{reason}, so the packets are {order} the right order")
+    }
+}
+
+/// Every comparison, and the sum of the ordered pairs' indices.
+struct Comparisons(Vec<Comparison>);
+
+impl Display for Comparisons {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let sum: usize = (self.0.iter().enumerate())
+            .filter(|(_, pair)| pair.left < pair.right)
+            .map(|(i, _)| i + 1)
+            .sum();
+        write!(f, "{sum} from the pairs in the right order")?;
+        for (i, pair) in self.0.iter().enumerate() {
+            write!(f, "\n\n== Pair {} ==\n{pair}", i + 1)?;
+        }
+        Ok(())
+    }
 }
 
 fn parse_packet(input: &str) -> nom::IResult<&str, Packet> {
     alt((
-        map(double, |n| Packet::Int(n as u32)),
+        map(i128, Packet::Int),
         map(
             delimited(
                 char('['),
-                separated_list0(char(','), parse_packet),
+                // past a comma there has to be another item
+                pair(
+                    opt(parse_packet),
+                    many0(preceded(char(','), cut(parse_packet))),
+                ),
                 char(']'),
             ),
-            Packet::List,
+            |(first, rest)| Packet::List(first.into_iter().chain(rest).collect()),
         ),
     ))(input)
 }
 
+/// What should have been where parsing failed. `after_comma` is for failures past the point
+/// of no return.
+fn expected(line: &str, rest: &str, after_comma: bool) -> &'static str {
+    let before = &line[..line.len() - rest.len()];
+    match rest.as_bytes() {
+        _ if before.is_empty() => "a packet",
+        // an integer that was too big for `i128`
+        [b'0'..=b'9', ..] | [b'-', b'0'..=b'9', ..] => "an integer that fits in 128 bits",
+        _ if after_comma => "an integer or a list",
+        _ if before.ends_with('[') => "an integer, a list or `]`",
+        _ => "`,` or `]`",
+    }
+}
+
 #[aoc_generator(day13)]
-fn generator(input: &str) -> Vec<(Packet, Packet)> {
+fn generator(input: &str) -> Result<Vec<(Packet, Packet)>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(13, input);
+    let packet = |line| Packet::parse(&ctx, line);
     input
         .split("\n\n")
         .map(|line| {
-            let (first, second) = line.split_once('\n').unwrap();
-            (
-                parse_packet(first).unwrap().1,
-                parse_packet(second).unwrap().1,
-            )
+            let (first, second) = ctx.split_once(line, "\n")?;
+            Ok((packet(first)?, packet(second)?))
         })
         .collect()
 }
@@ -72,6 +298,19 @@ fn ordered(input: &[(Packet, Packet)]) -> usize {
         .sum()
 }
 
+#[aoc(day13, part1, explain)]
+fn explain_ordered(input: &[(Packet, Packet)]) -> Comparisons {
+    Comparisons(
+        input
+            .iter()
+            .map(|(left, right)| Comparison {
+                left: left.clone(),
+                right: right.clone(),
+            })
+            .collect(),
+    )
+}
+
 #[aoc(day13, part2)]
 fn sorted(input: &[(Packet, Packet)]) -> usize {
     let mut flat: Vec<Packet> = input
@@ -87,3 +326,90 @@ fn sorted(input: &[(Packet, Packet)]) -> usize {
     let second = flat.binary_search(&divider_6).unwrap() + 1;
     first * second
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day13.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(ordered(&input), 13);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(sorted(&input), 140);
+    }
+
+    #[test]
+    fn round_trip() {
+        for line in EXAMPLE.lines().filter(|line| !line.is_empty()) {
+            let packet: Packet = line.parse().unwrap();
+            assert_eq!(packet.to_string(), line);
+            let json: Value = serde_json::from_str(line).unwrap();
+            assert_eq!(Value::from(&packet), json);
+            assert_eq!(Packet::try_from(&json), Ok(packet));
+        }
+        assert!("[1,2]]".parse::<Packet>().is_err());
+        let json = serde_json::json!([1, [2, "3"]]);
+        assert_eq!(Packet::try_from(&json), Err(NotAPacket("3".into())));
+    }
+
+    #[test]
+    fn comparison() {
+        let compare = |left: &str, right: &str| {
+            let (left, right) = (left.parse().unwrap(), right.parse().unwrap());
+            Comparison { left, right }.to_string()
+        };
+        assert_eq!(
+            compare("[1,1,3,1,1]", "[1,1,5,1,1]"),
+            "[1,1,3,1,1]\n     ^\n[1,1,5,1,1]\n     ^\n3 < 5, so the packets are in the right order"
+        );
+        assert_eq!(
+            compare("[[1],[2,3,4]]", "[[1],4]"),
+            "[[1],[2,3,4]]\n      ^\n[[1],4]\n     ^\n2 < 4, so the packets are in the right order"
+        );
+        assert_eq!(
+            compare("[7,7,7,7]", "[7,7,7]"),
+            "[7,7,7,7]\n       ^\n[7,7,7]\n      ^\nthe right side ran out first, so the packets are not in the right order"
+        );
+        assert_eq!(
+            compare("[[[]]]", "[[]]"),
+            "[[[]]]\n  ^\n[[]]\n  ^\nthe right side ran out first, so the packets are not in the right order"
+        );
+        assert!(compare("[1]", "[1]").ends_with("the packets are the same"));
+    }
+
+    #[test]
+    fn integers() {
+        let max = i128::MAX;
+        let line = format!("[{max},[-5,0]]");
+        let packet: Packet = line.parse().unwrap();
+        assert_eq!(packet.to_string(), line);
+        let json: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(Value::from(&packet), json);
+        assert_eq!(Packet::try_from(&json), Ok(packet));
+        assert!("[-1]".parse::<Packet>().unwrap() < "[0]".parse().unwrap());
+        let json: Value = serde_json::from_str("[1.5]").unwrap();
+        assert!(Packet::try_from(&json).is_err());
+
+        let error = |line: &str| {
+            let error = line.parse::<Packet>().unwrap_err();
+            (error.column, error.expected)
+        };
+        let overflow = format!("[1,{}]", max as u128 + 1);
+        assert_eq!(
+            error(&overflow),
+            (4, "an integer that fits in 128 bits".into())
+        );
+        assert_eq!(error("[1.5]"), (3, "`,` or `]`".into()));
+        assert_eq!(error("[1,]"), (4, "an integer or a list".into()));
+        assert_eq!(error("[x]"), (2, "an integer, a list or `]`".into()));
+        assert_eq!(error("[1]]"), (4, "the end of the packet".into()));
+        assert_eq!(error("x"), (1, "a packet".into()));
+    }
+}
@@ -1,41 +1,90 @@
+//! Sand falling into a cave, from the puzzle's source or from anywhere else with
+//! [`settled`].
+
+use crate::{
+    error::{ParseContext, ParseError},
+    grid::Grid,
+    input::normalize,
+    visualize::{Frame, Recorder},
+};
+
+/// A straight line of rock, along with the line of the input it's on
 #[derive(Debug, Clone, Copy)]
 enum Interval {
-    Horizontal { x: u32, y: u32, width: u32 },
-    Vertical { x: u32, y: u32, height: u32 },
+    Horizontal {
+        x: u32,
+        y: u32,
+        width: u32,
+        line: usize,
+    },
+    Vertical {
+        x: u32,
+        y: u32,
+        height: u32,
+        line: usize,
+    },
 }
 
+impl Interval {
+    fn cells(self) -> impl Iterator<Item = (u32, u32)> {
+        let (x, y, width, height) = match self {
+            Interval::Horizontal { x, y, width, .. } => (x, y, width, 1),
+            Interval::Vertical { x, y, height, .. } => (x, y, 1, height),
+        };
+        // the last cell can be at the very edge of `u32`
+        (y..=y + (height - 1)).flat_map(move |y| (x..=x + (width - 1)).map(move |x| (x, y)))
+    }
+
+    fn line(self) -> usize {
+        match self {
+            Interval::Horizontal { line, .. } | Interval::Vertical { line, .. } => line,
+        }
+    }
+}
+
+/// Where the sand comes from, in the puzzle
+const SOURCE: (u32, u32) = (500, 0);
+
 #[aoc_generator(day14)]
-fn generator(input: &str) -> Vec<Interval> {
-    input
-        .lines()
-        .flat_map(|line| {
-            line.split(" -> ")
-                .map(|xy| {
-                    let (x, y) = xy.split_once(',').unwrap();
-                    (x.parse().unwrap(), y.parse().unwrap())
-                })
-                .collect::<Vec<(u32, u32)>>()
-                .array_windows::<2>()
-                .map(|&[(x, y), (x2, y2)]| {
-                    if x == x2 {
-                        let (y, y2) = if y2 > y { (y, y2) } else { (y2, y) };
-                        Interval::Vertical {
-                            x,
-                            y,
-                            height: y2 - y + 1,
-                        }
-                    } else {
-                        let (x, x2) = if x2 > x { (x, x2) } else { (x2, x) };
-                        Interval::Horizontal {
-                            x,
-                            y,
-                            width: x2 - x + 1,
-                        }
-                    }
-                })
-                .collect::<Vec<_>>()
-        })
-        .collect()
+fn generator(input: &str) -> Result<Vec<Interval>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(14, input);
+    let mut intervals = vec![];
+    for (i, line) in input.lines().enumerate() {
+        let points = line
+            .split(" -> ")
+            .map(|xy| {
+                let (x, y) = ctx.split_once(xy, ",")?;
+                Ok((xy, (ctx.number(x)?, ctx.number(y)?)))
+            })
+            .collect::<Result<Vec<(&str, (u32, u32))>, ParseError>>()?;
+        for &[(_, (x, y)), (at, (x2, y2))] in points.array_windows::<2>() {
+            if x == x2 {
+                let (y, y2) = if y2 > y { (y, y2) } else { (y2, y) };
+                intervals.push(Interval::Vertical {
+                    x,
+                    y,
+                    height: (y2 - y)
+                        .checked_add(1)
+                        .ok_or_else(|| ctx.error(at, "a shorter line of rock"))?,
+                    line: i + 1,
+                });
+            } else if y == y2 {
+                let (x, x2) = if x2 > x { (x, x2) } else { (x2, x) };
+                intervals.push(Interval::Horizontal {
+                    x,
+                    y,
+                    width: (x2 - x)
+                        .checked_add(1)
+                        .ok_or_else(|| ctx.error(at, "a shorter line of rock"))?,
+                    line: i + 1,
+                });
+            } else {
+                return Err(ctx.error(at, "a point in line with the previous one"));
+            }
+        }
+    }
+    Ok(intervals)
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -45,77 +94,348 @@ enum State {
     Right,
 }
 
-fn blit(input: &[Interval], part2: bool) -> u32 {
-    const WIDTH: usize = 1024;
-    const HEIGHT: usize = 512;
-    let mut lowest = 0;
-    let mut grid = [[0u8; WIDTH]; HEIGHT];
-    for interval in input.iter() {
-        match *interval {
-            Interval::Horizontal { x, y, width } => {
-                for i in 0..width {
-                    grid[y as usize][x as usize + i as usize] = 1;
-                    lowest = lowest.max(y as usize);
-                }
-            }
-            Interval::Vertical { x, y, height } => {
-                for i in 0..height {
-                    grid[y as usize + i as usize][x as usize] = 1;
-                    lowest = lowest.max(y as usize + height as usize);
-                }
+fn draw(grid: &Grid<u8>) -> Frame {
+    grid.map(|&cell| b".#o"[cell as usize])
+}
+
+/// The most squares a cave can have, which is also how many bytes it takes
+const MAX_SQUARES: u64 = 1 << 28;
+
+/// An error about the cave as a whole rather than any one point, on the line that causes it
+fn cave_error(line: usize, expected: impl Into<String>, found: impl Into<String>) -> ParseError {
+    ParseError {
+        day: 14,
+        line,
+        column: 1,
+        expected: expected.into(),
+        found: found.into(),
+    }
+}
+
+/// Only the part of the cave that sand can get to, as it falls from `source`. That takes a
+/// byte for each square down to the bottom. With a floor, sand can pile up as wide as the
+/// cave is deep, so that's about twice the depth squared, and caves more than about 11000
+/// deep go over [`MAX_SQUARES`]. Without one, it's no wider than the rocks.
+#[derive(Debug, Clone)]
+struct Cave {
+    grid: Grid<u8>,
+    source: (usize, usize),
+    /// The lowest row that can stop sand, which is the floor if there is one
+    bottom: usize,
+}
+
+impl Cave {
+    /// Fails if there's nothing for sand to settle on, if the source is below it, or if the
+    /// cave would have more than [`MAX_SQUARES`].
+    fn new(input: &[Interval], (sx, sy): (u32, u32), floor: bool) -> Result<Cave, ParseError> {
+        let lowest = input
+            .iter()
+            .flat_map(|&rock| rock.cells().map(move |(_, y)| (y as i64, rock.line())))
+            .max_by_key(|&(y, _)| y);
+        // errors are down to the lowest rock, or the floor under it
+        let (bottom, line) = match (lowest, floor) {
+            (lowest, true) => lowest.map_or((2, 1), |(y, line)| (y + 2, line)),
+            (Some(lowest), false) => lowest,
+            (None, false) => return Err(cave_error(1, "a line of rock", "no rocks")),
+        };
+        let (sx, sy) = (sx as i64, sy as i64);
+        if sy > bottom {
+            let what = if floor {
+                "the floor"
+            } else {
+                "the lowest rock"
+            };
+            return Err(cave_error(
+                line,
+                format!("something below the source at {sx},{sy}"),
+                format!("{what} at y = {bottom}"),
+            ));
+        }
+        // sand piles up no wider than it is tall
+        let depth = bottom - sy;
+        let (mut left, mut right) = (sx - depth, sx + depth);
+        if !floor {
+            // past the rocks sand falls straight through, but it needs a column to do so
+            let xs = input.iter().flat_map(|i| i.cells()).map(|(x, _)| x as i64);
+            let (min, max) = xs.fold((i64::MAX, i64::MIN), |(min, max), x| {
+                (min.min(x), max.max(x))
+            });
+            (left, right) = (left.max(min - 1), right.min(max + 1));
+            if !(left..=right).contains(&sx) {
+                (left, right) = (sx, sx);
             }
         }
+        let (width, height) = ((right - left + 1) as u64, bottom as u64 + 1);
+        if !width
+            .checked_mul(height)
+            .is_some_and(|squares| squares <= MAX_SQUARES)
+        {
+            return Err(cave_error(
+                line,
+                format!("a cave of at most {MAX_SQUARES} squares"),
+                format!("one {width} wide and {height} deep"),
+            ));
+        }
+        let mut grid = Grid::new(width as usize, height as usize, 0u8);
+        let rocks = input.iter().flat_map(|i| i.cells());
+        for (x, y) in rocks.filter(|&(x, _)| (left..=right).contains(&(x as i64))) {
+            grid[((x as i64 - left) as usize, y as usize)] = 1;
+        }
+        if floor {
+            grid.row_mut(bottom as usize).fill(1);
+        }
+        Ok(Cave {
+            grid,
+            source: ((sx - left) as usize, sy as usize),
+            bottom: bottom as usize,
+        })
     }
 
-    if part2 {
-        for i in 0..WIDTH {
-            grid[lowest + 2][i] = 1;
+    /// Drops sand until it stops settling, and returns how many grains did.
+    fn fill(&mut self, recorder: &mut Recorder) -> u32 {
+        let (sx, sy) = self.source;
+        let grid = &mut self.grid;
+        let mut x = sx;
+        let mut y = sy;
+        let mut state = State::Falling;
+        let mut count = 0;
+        while y <= self.bottom {
+            if grid[(x, y)] == 0 {
+                y += 1;
+                state = State::Falling;
+            } else {
+                if y == sy {
+                    break;
+                }
+                match state {
+                    State::Falling => {
+                        x -= 1;
+                        state = State::Left;
+                    }
+                    State::Left => {
+                        x += 2;
+                        state = State::Right;
+                    }
+                    State::Right => {
+                        x -= 1;
+                        y -= 1;
+                        grid[(x, y)] = 2;
+                        count += 1;
+                        recorder.record(|| draw(grid));
+                        x = sx;
+                        y = sy;
+                        state = State::Falling;
+                    }
+                }
+            }
         }
-        lowest += 2;
+        count
     }
 
-    let mut x = 500;
-    let mut y = 0;
-    let mut state = State::Falling;
-    let mut count = 0;
-    while y <= lowest {
-        if grid[y][x] == 0 {
-            y += 1;
-            state = State::Falling;
-        } else {
-            if y == 0 {
+    /// Like [`Cave::fill`], but each grain carries on from where the last one came to rest
+    /// rather than falling all the way from the source again.
+    fn fill_fast(&mut self) -> u32 {
+        let grid = &mut self.grid;
+        let mut path = vec![self.source];
+        let mut count = 0;
+        while let Some(&(x, y)) = path.last() {
+            // falling past the lowest rock, so no more sand will settle
+            if y == self.bottom {
                 break;
             }
-            match state {
-                State::Falling => {
-                    x -= 1;
-                    state = State::Left;
-                }
-                State::Left => {
-                    x += 2;
-                    state = State::Right;
-                }
-                State::Right => {
-                    x -= 1;
-                    y -= 1;
-                    grid[y][x] = 1;
+            // without a floor, sand can fall down the column at either edge of the grid
+            match [Some(x), x.checked_sub(1), Some(x + 1)]
+                .into_iter()
+                .flatten()
+                .find(|&x| x < grid.width() && grid[(x, y + 1)] == 0)
+            {
+                Some(x) => path.push((x, y + 1)),
+                None => {
+                    grid[(x, y)] = 2;
                     count += 1;
-                    x = 500;
-                    y = 0;
-                    state = State::Falling;
+                    path.pop();
                 }
             }
         }
+        count
     }
-    count
+
+    /// With a floor, sand ends up everywhere it can get to, so it's enough to work out where
+    /// that is, row by row.
+    fn reachable(&self) -> u32 {
+        let (sx, sy) = self.source;
+        let mut row = vec![false; self.grid.width()];
+        row[sx] = self.grid[(sx, sy)] == 0;
+        let mut count = row[sx] as u32;
+        for y in sy + 1..self.bottom {
+            let above = row.clone();
+            for (x, cell) in row.iter_mut().enumerate() {
+                let from = &above[x.saturating_sub(1)..(x + 2).min(above.len())];
+                *cell = self.grid[(x, y)] == 0 && from.contains(&true);
+                count += *cell as u32;
+            }
+        }
+        count
+    }
+}
+
+fn blit(
+    input: &[Interval],
+    source: (u32, u32),
+    floor: bool,
+    recorder: &mut Recorder,
+) -> Result<u32, ParseError> {
+    Ok(Cave::new(input, source, floor)?.fill(recorder))
+}
+
+/// How many grains of sand come to rest when they fall from `source` instead of `500,0`,
+/// with or without a floor two below the lowest rock.
+pub fn settled(input: &str, source: (u32, u32), floor: bool) -> Result<u32, ParseError> {
+    let mut cave = Cave::new(&generator(input)?, source, floor)?;
+    Ok(if floor {
+        cave.reachable()
+    } else {
+        cave.fill_fast()
+    })
 }
 
 #[aoc(day14, part1)]
-fn void(input: &[Interval]) -> u32 {
-    blit(input, false)
+fn void(input: &[Interval]) -> Result<u32, ParseError> {
+    blit(input, SOURCE, false, &mut Recorder::off())
 }
 
 #[aoc(day14, part2)]
-fn floor(input: &[Interval]) -> u32 {
-    blit(input, true)
+fn floor(input: &[Interval]) -> Result<u32, ParseError> {
+    blit(input, SOURCE, true, &mut Recorder::off())
+}
+
+#[aoc(day14, part1, fast)]
+fn void_fast(input: &[Interval]) -> Result<u32, ParseError> {
+    Ok(Cave::new(input, SOURCE, false)?.fill_fast())
+}
+
+#[aoc(day14, part2, fast)]
+fn floor_fast(input: &[Interval]) -> Result<u32, ParseError> {
+    Ok(Cave::new(input, SOURCE, true)?.reachable())
+}
+
+/// Records a frame each time a grain of sand comes to rest
+pub(crate) fn visualize(input: &str, part: u32, recorder: &mut Recorder) -> Result<(), ParseError> {
+    blit(&generator(input)?, SOURCE, part == 2, recorder)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day14.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(void(&input).unwrap(), 24);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(floor(&input).unwrap(), 93);
+    }
+
+    #[test]
+    fn cave() {
+        let input = generator(EXAMPLE).unwrap();
+        // sand can spread as far either side as the floor is deep
+        let cave = Cave::new(&input, SOURCE, true).unwrap();
+        assert_eq!((cave.grid.width(), cave.grid.height()), (23, 12));
+        assert_eq!(Cave::new(&input, SOURCE, false).unwrap().grid.width(), 12);
+
+        // the same cave moved far to the right and down still fills up the same
+        let far = "1000498,1004 -> 1000498,1006 -> 1000496,1006\n\
+                   1000503,1004 -> 1000502,1004 -> 1000502,1009 -> 1000494,1009";
+        let far = generator(far).unwrap();
+        assert_eq!(
+            blit(&far, (1000500, 1000), false, &mut Recorder::off()).unwrap(),
+            24
+        );
+        assert_eq!(
+            blit(&far, (1000500, 1000), true, &mut Recorder::off()).unwrap(),
+            93
+        );
+        // and from above the rocks but off to one side, sand falls straight past them
+        assert_eq!(blit(&far, SOURCE, false, &mut Recorder::off()).unwrap(), 0);
+        assert_eq!(Cave::new(&far, SOURCE, false).unwrap().grid.width(), 1);
+
+        // a deep cave's pile is wider than the old fixed grid
+        let deep = generator("500,1200 -> 501,1200").unwrap();
+        let cave = Cave::new(&deep, SOURCE, true).unwrap();
+        assert_eq!(cave.grid.width(), 2 * 1202 + 1);
+        assert_eq!(cave.source, (1202, 0));
+        // sand gets everywhere above the floor but the two rocks
+        let deeper = generator("500,2000 -> 501,2000").unwrap();
+        assert_eq!(floor_fast(&deeper).unwrap(), 2002 * 2002 - 2);
+        // without a floor, only as wide as the rocks, however deep they are
+        let ledge = generator("499,1000000 -> 501,1000000").unwrap();
+        assert_eq!(Cave::new(&ledge, SOURCE, false).unwrap().grid.width(), 5);
+        assert_eq!(void_fast(&ledge).unwrap(), 1);
+        assert_eq!(settled("499,9 -> 501,9", (500, 0), false), Ok(1));
+    }
+
+    #[test]
+    fn errors() {
+        // a source below everything has nothing to fill
+        let input = generator(EXAMPLE).unwrap();
+        let below = Cave::new(&input, (500, 10), false).unwrap_err();
+        assert_eq!(
+            (below.line, below.found.as_str()),
+            (2, "the lowest rock at y = 9")
+        );
+        assert_eq!(Cave::new(&input, (500, 11), true).unwrap().bottom, 11);
+        assert!(Cave::new(&input, (500, 12), true).is_err());
+        assert!(settled("", SOURCE, false).is_err());
+        assert_eq!(settled("", SOURCE, true), Ok(4));
+
+        // and some caves are too big to hold in memory
+        let deep = generator("500,200000 -> 501,200000").unwrap();
+        let error = floor(&deep).unwrap_err();
+        assert_eq!(error.found, "one 400005 wide and 200003 deep");
+        let ledge = "500,2000000000 -> 501,2000000000";
+        assert!(settled(ledge, SOURCE, false).is_err());
+        // right at the edge of the coordinates, and past it
+        let edge = "500,4294967295 -> 501,4294967295";
+        assert!(void(&generator(edge).unwrap()).is_err());
+        assert!(floor(&generator(edge).unwrap()).is_err());
+        let long = generator("0,0 -> 4294967295,0").unwrap_err();
+        assert_eq!((long.line, long.column), (1, 8));
+    }
+
+    #[test]
+    fn edges() {
+        // the grid is only as wide as the rocks, so sand falls down its first column
+        let input = generator("500,5 -> 500,5\n510,9 -> 510,9").unwrap();
+        let mut slow = Cave::new(&input, SOURCE, false).unwrap();
+        let mut fast = slow.clone();
+        assert_eq!(slow.fill(&mut Recorder::off()), 0);
+        assert_eq!(fast.fill_fast(), 0);
+        // and down its last
+        let input = generator("499,5 -> 500,5\n490,9 -> 490,9").unwrap();
+        assert_eq!(void_fast(&input).unwrap(), 0);
+    }
+
+    #[test]
+    fn fast() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(void_fast(&input).unwrap(), 24);
+        assert_eq!(floor_fast(&input).unwrap(), 93);
+        let mut cave = Cave::new(&input, SOURCE, true).unwrap();
+        assert_eq!(cave.fill_fast(), 93);
+
+        // both ways of filling leave the sand in the same places
+        let mut slow = Cave::new(&input, SOURCE, false).unwrap();
+        let mut fast = slow.clone();
+        slow.fill(&mut Recorder::off());
+        fast.fill_fast();
+        assert_eq!(draw(&slow.grid).to_string(), draw(&fast.grid).to_string());
+    }
 }
@@ -1,207 +1,267 @@
-use std::{cmp::Ordering, ops::Add};
+//! Beacon sensors, and a [`SensorField`] for finding where else beacons could be.
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-struct Entry {
-    sensor: (i64, i64),
-    closest: (i64, i64),
-}
+use fxhash::FxHashSet;
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct Interval {
-    start: i64,
-    end: i64,
-}
+use crate::{
+    error::{ParseContext, ParseError},
+    geometry::{Aabb, Point2},
+    input::normalize,
+    interval::{Interval, IntervalSet},
+};
 
-impl Interval {
-    fn len(&self) -> i64 {
-        (self.start - self.end).abs()
-    }
-
-    fn unit(x: i64) -> Interval {
-        Interval { start: x, end: x }
-    }
+/// A sensor and the beacon closest to it, which no other beacon is as close as.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Entry {
+    pub sensor: Point2<i64>,
+    pub closest: Point2<i64>,
 }
 
-// Addition expands the bounds
-impl Add for Interval {
-    type Output = Self;
-
-    fn add(self, other: Self) -> Self::Output {
-        Interval {
-            start: self.start.min(other.start),
-            end: self.end.max(other.end),
-        }
+impl Entry {
+    fn radius(&self) -> i64 {
+        self.sensor.manhattan_distance(self.closest)
     }
-}
 
-impl Ord for Interval {
-    fn cmp(&self, other: &Self) -> Ordering {
-        // all overlapping intervals are equally ordered
-        if self.end < other.start {
-            Ordering::Less
-        } else if other.end < self.start {
-            Ordering::Greater
+    /// The part of a row within range of the sensor, if any
+    fn row_interval(&self, row: i64) -> Option<Interval<i64>> {
+        let radius_manhattan = self.radius();
+        let y_offset = (row - self.sensor.y).abs();
+        if y_offset <= radius_manhattan {
+            let width = radius_manhattan - y_offset;
+            Some(Interval::new(self.sensor.x - width, self.sensor.x + width))
         } else {
-            Ordering::Equal
+            None
         }
     }
 }
 
-impl PartialOrd for Interval {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
-    }
+/// Everything the sensors can tell about the area around them.
+#[derive(Debug, Clone)]
+pub struct SensorField {
+    entries: Vec<Entry>,
 }
 
-// For more efficient algorithmic complexity, this should use some form of
-// interval tree for faster insertion.
-//
-// However, the number of intervals in the input is very small (less than a hundred),
-// so a vector ends up being faster.
-struct IntervalList {
-    /// Must stay strictly increasing
-    intervals: Vec<Interval>,
-}
+impl SensorField {
+    pub fn new(entries: &[Entry]) -> Self {
+        SensorField {
+            entries: entries.to_vec(),
+        }
+    }
 
-impl IntervalList {
-    fn new() -> Self {
-        Self { intervals: vec![] }
+    /// The parts of a row within range of any sensor
+    fn coverage(&self, row: i64) -> IntervalSet<i64> {
+        let mut intervals = IntervalSet::new();
+        self.cover(row, &mut intervals);
+        intervals
     }
 
-    fn add_interval(&mut self, interval: Interval) {
-        match self.intervals.binary_search(&interval) {
-            Ok(_) => {
-                // An overlapping interval exists; take their union
-                // We maintain strict order to ensure there are no overlaps after add()
-                let eq_start = self.intervals.partition_point(|&x| x < interval);
-                // -1 because the partition_point interval is exclusive
-                let eq_end = self.intervals.partition_point(|&x| x <= interval) - 1;
-                let merged = self.intervals[eq_start] + self.intervals[eq_end] + interval;
-                // Replace the range with this one element
-                self.intervals.drain(eq_start + 1..=eq_end);
-                self.intervals[eq_start] = merged
-            }
-            Err(i) => {
-                self.intervals.insert(i, interval);
+    fn cover(&self, row: i64, intervals: &mut IntervalSet<i64>) {
+        intervals.clear();
+        for entry in &self.entries {
+            if let Some(interval) = entry.row_interval(row) {
+                intervals.insert(interval);
             }
         }
     }
 
-    fn total_span(&self) -> i64 {
-        self.intervals.iter().map(Interval::len).sum()
+    /// How many positions in a row can't be a beacon: those within range of a sensor,
+    /// except for the beacons that are already known.
+    pub fn no_beacon(&self, row: i64) -> i64 {
+        let beacons: FxHashSet<_> = self
+            .entries
+            .iter()
+            .map(|entry| entry.closest)
+            .filter(|beacon| beacon.y == row)
+            .collect();
+        self.coverage(row).len() - beacons.len() as i64
     }
 
-    fn first_gap(&self, interval: Interval) -> Option<i64> {
-        let start = self
-            .intervals
-            .binary_search(&Interval::unit(interval.start));
-        let end = self.intervals.binary_search(&Interval::unit(interval.end));
-        match (start, end) {
-            (Ok(i), Ok(j)) if i == j => None,
-            (Ok(i), Ok(j)) if i != j => Some(self.intervals[i].end + 1),
-            (_, Err(_)) => Some(interval.end),
-            (Err(_), _) => Some(interval.start),
-            _ => todo!(),
-        }
+    /// The positions out of range of every sensor, row by row.
+    pub fn uncovered(&self, area: Aabb<i64>) -> impl Iterator<Item = Point2<i64>> + '_ {
+        let columns = Interval::new(area.min.x, area.max.x);
+        // reused for every row, which saves a lot of allocating
+        let mut intervals = IntervalSet::new();
+        (area.min.y..=area.max.y).flat_map(move |y| {
+            self.cover(y, &mut intervals);
+            let gaps: Vec<_> = intervals.gaps(columns).collect();
+            gaps.into_iter()
+                .flat_map(move |gap| (gap.start..=gap.end).map(move |x| Point2::new(x, y)))
+        })
     }
 
-    fn clear(&mut self) {
-        self.intervals.clear();
+    /// Like [`SensorField::uncovered`], without going row by row. Turned 45 degrees, to
+    /// `u = x + y` and `v = x - y`, each sensor's range is a square. The lines just outside
+    /// the squares cut the area into blocks that are either entirely in range of some sensor
+    /// or entirely out of range, so only the blocks need checking rather than every row.
+    fn uncovered_rotated(&self, area: Aabb<i64>) -> Vec<Point2<i64>> {
+        let (Point2 { x: x0, y: y0 }, Point2 { x: x1, y: y1 }) = (area.min, area.max);
+        let mut us = vec![x0 + y0, x1 + y1 + 1];
+        let mut vs = vec![x0 - y1, x1 - y0 + 1];
+        let squares: Vec<_> = (self.entries.iter())
+            .map(|entry| {
+                let (u, v, r) = (
+                    entry.sensor.x + entry.sensor.y,
+                    entry.sensor.x - entry.sensor.y,
+                    entry.radius(),
+                );
+                (u - r..=u + r, v - r..=v + r)
+            })
+            .collect();
+        for (u, v) in &squares {
+            us.extend([*u.start(), u.end() + 1]);
+            vs.extend([*v.start(), v.end() + 1]);
+        }
+        for lines in [&mut us, &mut vs] {
+            // the area's own edges come first, and nothing outside of them matters
+            let (first, last) = (lines[0], lines[1]);
+            lines.retain(|&line| (first..=last).contains(&line));
+            lines.sort_unstable();
+            lines.dedup();
+        }
+
+        let mut uncovered = vec![];
+        for u_block in us.windows(2) {
+            for v_block in vs.windows(2) {
+                let (u_start, v_start) = (u_block[0], v_block[0]);
+                if squares
+                    .iter()
+                    .any(|(u, v)| u.contains(&u_start) && v.contains(&v_start))
+                {
+                    continue;
+                }
+                // only the parts of the block that are inside the area, and where `u` and
+                // `v` agree on whether they're odd
+                let (v_min, v_max) = (v_start, v_block[1] - 1);
+                let u_min = u_start.max(2 * x0 - v_max).max(v_min + 2 * y0);
+                let u_max = (u_block[1] - 1).min(2 * x1 - v_min).min(v_max + 2 * y1);
+                for u in u_min..=u_max {
+                    let low = v_min.max(2 * x0 - u).max(u - 2 * y1);
+                    let high = v_max.min(2 * x1 - u).min(u - 2 * y0);
+                    let low = low + (u + low).rem_euclid(2);
+                    for v in (low..=high).step_by(2) {
+                        uncovered.push(Point2::new((u + v) / 2, (u - v) / 2));
+                    }
+                }
+            }
+        }
+        uncovered.sort_unstable_by_key(|point| (point.y, point.x));
+        uncovered
     }
 }
 
 #[aoc_generator(day15)]
-fn generator(input: &str) -> Vec<Entry> {
+fn generator(input: &str) -> Result<Vec<Entry>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(15, input);
     input
         .lines()
         .map(|line| {
-            let (sx, sy, bx, by) = scan_fmt::scan_fmt!(
-                line,
-                "Sensor at x={}, y={}: closest beacon is at x={}, y={}",
-                i64,
-                i64,
-                i64,
-                i64
-            )
-            .unwrap();
-            Entry {
-                sensor: (sx, sy),
-                closest: (bx, by),
-            }
+            let rest = ctx.strip_prefix(line, "Sensor at x=")?;
+            let (sx, rest) = ctx.split_once(rest, ", y=")?;
+            let (sy, rest) = ctx.split_once(rest, ": closest beacon is at x=")?;
+            let (bx, by) = ctx.split_once(rest, ", y=")?;
+            Ok(Entry {
+                sensor: Point2::new(ctx.number(sx)?, ctx.number(sy)?),
+                closest: Point2::new(ctx.number(bx)?, ctx.number(by)?),
+            })
         })
         .collect()
 }
 
+const CHECKED_SLICE: i64 = 2_000_000;
+const CHECKED_SIZE: i64 = 4_000_000;
+
 #[aoc(day15, part1)]
 fn one_row(input: &[Entry]) -> i64 {
-    const CHECKED_SLICE: i64 = 2_000_000;
-    let mut intervals = IntervalList::new();
-    input
-        .iter()
-        .copied()
-        .filter_map(
-            |Entry {
-                 sensor: (sx, sy),
-                 closest: (bx, by),
-             }| {
-                let radius_manhattan = (sx - bx).abs() + (sy - by).abs();
-                let y_offset = (CHECKED_SLICE - sy).abs();
-                if y_offset <= radius_manhattan {
-                    let width = radius_manhattan - y_offset;
-                    Some(Interval {
-                        start: sx - width,
-                        end: sx + width,
-                    })
-                } else {
-                    None
-                }
-            },
-        )
-        .for_each(|interval| {
-            intervals.add_interval(interval);
-        });
-
-    intervals.total_span()
+    SensorField::new(input).no_beacon(CHECKED_SLICE)
 }
 
 #[aoc(day15, part2)]
 fn full_grid(input: &[Entry]) -> i64 {
-    const CHECKED_SIZE: i64 = 4_000_000;
-    // The approach from part 1 doesn't work due to the lack of an "efficient" ordering over the grid.
-    let full_range = Interval {
-        start: 0,
-        end: CHECKED_SIZE,
-    };
-    let mut intervals = IntervalList::new();
-    let mut i = 0;
-    while i < CHECKED_SIZE {
-        let it = input.iter().copied().filter_map(
-            |Entry {
-                 sensor: (sx, sy),
-                 closest: (bx, by),
-             }| {
-                let radius_manhattan = (sx - bx).abs() + (sy - by).abs();
-                let y_offset = (i - sy).abs();
-                if y_offset <= radius_manhattan {
-                    let width = radius_manhattan - y_offset;
-                    Some(Interval {
-                        start: sx - width,
-                        end: sx + width,
-                    })
-                } else {
-                    None
-                }
-            },
+    tuning_frequency(input, CHECKED_SIZE)
+}
+
+#[aoc(day15, part2, rotated)]
+fn full_grid_rotated(input: &[Entry]) -> i64 {
+    let field = SensorField::new(input);
+    frequency(field.uncovered_rotated(search_area(CHECKED_SIZE))[0])
+}
+
+fn search_area(size: i64) -> Aabb<i64> {
+    Aabb {
+        min: Point2::new(0, 0),
+        max: Point2::new(size, size),
+    }
+}
+
+fn tuning_frequency(input: &[Entry], size: i64) -> i64 {
+    let field = SensorField::new(input);
+    let beacon = field.uncovered(search_area(size)).next().unwrap();
+    frequency(beacon)
+}
+
+// the multiplier is fixed, regardless of the search area
+fn frequency(beacon: Point2<i64>) -> i64 {
+    beacon.x * 4_000_000 + beacon.y
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day15.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(SensorField::new(&input).no_beacon(10), 26);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(tuning_frequency(&input, 20), 56000011);
+    }
+
+    #[test]
+    fn sensor_field() {
+        let input = generator(EXAMPLE).unwrap();
+        let field = SensorField::new(&input);
+        // the beacon at 2,10 is the only one in that row
+        assert_eq!(field.coverage(10).len(), 27);
+        let coverage = field.coverage(10);
+        let gaps: Vec<_> = coverage.gaps(Interval::new(-10, 30)).collect();
+        assert_eq!(gaps, vec![Interval::new(-10, -3), Interval::new(25, 30)]);
+
+        let area = |min: (i64, i64), max: (i64, i64)| Aabb {
+            min: min.into(),
+            max: max.into(),
+        };
+        let uncovered: Vec<_> = field.uncovered(area((0, 0), (20, 20))).collect();
+        assert_eq!(uncovered, vec![Point2::new(14, 11)]);
+        // a sensor's own position is always covered, somewhere far off never is
+        assert_eq!(field.uncovered(area((2, 18), (2, 18))).count(), 0);
+        assert_eq!(field.uncovered(area((-20, -20), (-19, -20))).count(), 2);
+    }
+
+    #[test]
+    fn rotated() {
+        let input = generator(EXAMPLE).unwrap();
+        let field = SensorField::new(&input);
+        assert_eq!(
+            field.uncovered_rotated(search_area(20)),
+            vec![Point2::new(14, 11)]
         );
-        for interval in it {
-            intervals.add_interval(interval);
-        }
-        if let Some(gap) = intervals.first_gap(full_range) {
-            return gap * CHECKED_SIZE + i;
+        for (min, max) in [
+            ((-5, -5), (30, 30)),
+            ((-3, 7), (4, 12)),
+            ((12, 10), (12, 10)),
+        ] {
+            let area = Aabb {
+                min: min.into(),
+                max: max.into(),
+            };
+            let scanned: Vec<_> = field.uncovered(area).collect();
+            assert_eq!(field.uncovered_rotated(area), scanned);
         }
-        i += 1;
-        intervals.clear();
-        continue;
     }
-    unreachable!()
 }
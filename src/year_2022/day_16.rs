@@ -1,8 +1,11 @@
-// Today has been needlessly difficult. I unfortunately don't have the time for a better implementation
+use std::{collections::HashMap, fmt};
 
-use std::{
-    cmp::Ordering,
-    collections::{BinaryHeap, HashMap},
+use fxhash::FxHashMap;
+
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+    search::{self, Path},
 };
 
 type Name = (u8, u8);
@@ -14,347 +17,276 @@ struct Valve {
     connections: Vec<Name>,
 }
 
+fn parse_name(ctx: &ParseContext, name: &str) -> Result<Name, ParseError> {
+    match name.as_bytes() {
+        &[a, b] if name.bytes().all(|c| c.is_ascii_uppercase()) => Ok((a, b)),
+        _ => Err(ctx.error(name, "a two-letter valve name")),
+    }
+}
+
 #[aoc_generator(day16)]
-fn generator(input: &str) -> Vec<Valve> {
+fn generator(input: &str) -> Result<Vec<Valve>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(16, input);
     input
         .lines()
         .map(|line| {
-            let (first, conn) = line.split_once(';').unwrap();
-            let (head, flow) = first.split_once(" has flow rate=").unwrap();
-            let name = (head.as_bytes()[6], head.as_bytes()[7]);
-            let rate = flow.parse().unwrap();
+            let (first, conn) = ctx.split_once(line, "; ")?;
+            let head = ctx.strip_prefix(first, "Valve ")?;
+            let (head, flow) = ctx.split_once(head, " has flow rate=")?;
+            let name = parse_name(&ctx, head)?;
+            let rate = ctx.number(flow)?;
+            let conn = conn
+                .strip_prefix("tunnels lead to valves ")
+                .or_else(|| conn.strip_prefix("tunnel leads to valve "))
+                .ok_or_else(|| ctx.error(conn, "`tunnels lead to valves <names>`"))?;
             let connections = conn
                 .split(", ")
-                .map(|n| {
-                    if n.len() == 2 {
-                        n.as_bytes()
-                    } else {
-                        let b = n.as_bytes();
-                        &b[b.len() - 2..]
-                    }
-                })
-                .filter_map(|n| (n.len() == 2).then_some((n[0], n[1])))
-                .collect();
-            Valve {
+                .map(|n| parse_name(&ctx, n))
+                .collect::<Result<_, _>>()?;
+            Ok(Valve {
                 name,
                 rate,
                 connections,
-            }
+            })
         })
         .collect()
 }
 
-#[derive(PartialEq, Eq, Clone, Copy, Debug)]
-struct Hole {
-    name: Name,
-    minutes: u8,
-    loss: u32,
-    vented: u64,
+/// The valves worth opening, and the time it takes to walk between them
+struct Network {
+    names: Vec<Name>,
+    rates: Vec<u32>,
+    /// From each valve worth opening, and then from the start, to each valve worth opening
+    distances: Vec<Vec<usize>>,
 }
 
-impl Hole {
-    fn key(&self) -> (u8, u64, Name) {
-        (self.minutes, self.vented, self.name)
+impl Network {
+    fn new(input: &[Valve]) -> Network {
+        let neighbors: HashMap<_, _> = input
+            .iter()
+            .map(|valve| (valve.name, valve.connections.as_slice()))
+            .collect();
+        let suspicious: Vec<_> = input.iter().filter(|valve| valve.rate > 0).collect();
+        let start = (b'A', b'A');
+
+        let distances = suspicious
+            .iter()
+            .map(|valve| valve.name)
+            .chain([start])
+            .map(|source| {
+                let from_source = search::bfs_distances(source, |name| {
+                    neighbors
+                        .get(name)
+                        .copied()
+                        .unwrap_or_default()
+                        .iter()
+                        .copied()
+                });
+                suspicious
+                    .iter()
+                    .map(|valve| from_source.get(&valve.name).copied().unwrap_or(usize::MAX))
+                    .collect()
+            })
+            .collect();
+        Network {
+            names: suspicious.iter().map(|valve| valve.name).collect(),
+            rates: suspicious.iter().map(|valve| valve.rate).collect(),
+            distances,
+        }
     }
-}
 
-impl Ord for Hole {
-    fn cmp(&self, other: &Self) -> Ordering {
-        other
-            .loss
-            .cmp(&self.loss)
-            .then_with(|| other.name.cmp(&self.name))
-            .then_with(|| other.vented.cmp(&self.vented))
-            .then_with(|| other.minutes.cmp(&self.minutes))
-        // not consistent with partialeq
+    /// The most pressure that can be released by opening each set of valves, and the order
+    /// to open them in
+    fn best_openings(&self, minutes: usize) -> FxHashMap<u64, Path<usize, u32>> {
+        let start = self.rates.len();
+        search::best_subsets(
+            self.rates.len(),
+            (start, minutes),
+            0,
+            |&(at, minutes), &released, valve| {
+                // walk there, then take a minute to open it
+                let minutes = minutes
+                    .checked_sub(self.distances[at][valve].saturating_add(1))
+                    .filter(|&minutes| minutes > 0)?;
+                Some((
+                    (valve, minutes),
+                    released + self.rates[valve] * minutes as u32,
+                ))
+            },
+        )
     }
-}
 
-impl PartialOrd for Hole {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+    /// Replays an order of opening valves, minute by minute
+    fn openings(&self, order: &[usize], minutes: usize) -> Vec<Opening> {
+        let mut at = self.rates.len();
+        let mut minute = 0;
+        order
+            .iter()
+            .map(|&valve| {
+                minute += self.distances[at][valve] + 1;
+                at = valve;
+                Opening {
+                    name: self.names[valve],
+                    minute,
+                    released: self.rates[valve] * (minutes - minute) as u32,
+                }
+            })
+            .collect()
     }
 }
 
-#[derive(Debug, PartialEq, Eq, Clone, Copy)]
-struct DoubleHole {
-    me: Hole,
-    your_name: Name,
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Opening {
+    name: Name,
+    /// The minute spent opening the valve, counting from 1
+    minute: usize,
+    /// Everything it releases until the time runs out
+    released: u32,
 }
 
-impl DoubleHole {
-    fn key(&self) -> ((u8, u64, Name), Name) {
-        (self.me.key(), self.your_name)
-    }
+/// Which valves each of us opens, and when
+#[derive(Debug)]
+struct Plan {
+    agents: Vec<(&'static str, Vec<Opening>)>,
 }
 
-impl Ord for DoubleHole {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.me
-            .cmp(&other.me)
-            .then_with(|| self.your_name.cmp(&other.your_name))
+impl Plan {
+    fn released(&self) -> u32 {
+        self.agents
+            .iter()
+            .flat_map(|(_, openings)| openings)
+            .map(|opening| opening.released)
+            .sum()
     }
 }
 
-impl PartialOrd for DoubleHole {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl fmt::Display for Plan {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} pressure released", self.released())?;
+        for (agent, openings) in &self.agents {
+            write!(f, "\n{agent}:")?;
+            for opening in openings {
+                let (a, b) = opening.name;
+                write!(
+                    f,
+                    "\n  minute {}: open {}{} (+{})",
+                    opening.minute, a as char, b as char, opening.released
+                )?;
+            }
+        }
+        Ok(())
     }
 }
 
-fn compute_loss(vented: u64, losers: usize, sus: &[Valve]) -> u32 {
-    (0..losers)
-        .filter_map(|i| (vented & (1 << i) == 0).then_some(sus[i].rate))
-        .sum()
+fn solo_plan(input: &[Valve]) -> Plan {
+    let network = Network::new(input);
+    let best = network.best_openings(30);
+    let order = best
+        .values()
+        .max_by_key(|path| path.cost)
+        .map(|path| path.states.as_slice())
+        .unwrap_or_default();
+    Plan {
+        agents: vec![("you", network.openings(order, 30))],
+    }
 }
 
-#[aoc(day16, part1)]
-fn solo_vent(input: &[Valve]) -> u32 {
-    let minutes_left = 30;
-
-    let suspicious: Vec<_> = input
+fn pair_plan(input: &[Valve]) -> Plan {
+    let network = Network::new(input);
+    let best = network.best_openings(26);
+    // the elephant and I open separate sets of valves, so our efforts just add up
+    let mut openings: Vec<_> = best
         .iter()
-        .filter(|valve| valve.rate > 0)
-        .cloned()
+        .map(|(&valves, path)| (path.cost, valves))
         .collect();
+    openings.sort_unstable_by(|a, b| b.cmp(a));
 
-    let total_possible_loss: u32 = suspicious
-        .iter()
-        .map(|valve| valve.rate * minutes_left as u32)
-        .sum();
-    let losers = suspicious.len();
-
-    let mut neighbors = HashMap::new();
-    for valve in input.iter() {
-        neighbors.insert(valve.name, valve.connections.clone());
-    }
-
-    let start = (b'A', b'A');
-    // we want the shortest path that visits all relievers in the order that minimizes loss
-    // loss = total flow not being released per tick
-    let mut heap = BinaryHeap::new();
-    let first_hole = Hole {
-        name: start,
-        minutes: minutes_left,
-        loss: 0,
-        vented: 0,
-    };
-    heap.push(first_hole);
-
-    let mut min_loss_for = HashMap::new();
-    min_loss_for.insert(first_hole.key(), 0u32);
-    let mut min_loss = u32::MAX;
-
-    while let Some(hole) = heap.pop() {
-        if hole.minutes == 0 {
-            min_loss = min_loss.min(hole.loss);
-            break;
-        }
-        if hole.vented == (1 << losers) - 1 {
-            min_loss = min_loss.min(hole.loss);
+    let mut best_pair = (0, 0, 0);
+    for (i, &(mine, my_valves)) in openings.iter().enumerate() {
+        // the rest are sorted, so can't make up the difference
+        if mine * 2 <= best_pair.0 {
             break;
         }
-
-        if hole.loss > min_loss_for.get(&hole.key()).copied().unwrap_or(u32::MAX) {
-            continue;
-        }
-
-        if let Some(index) = (0..losers).find(|&i| suspicious[i].name == hole.name) {
-            if hole.vented & (1 << index) == 0 {
-                let vent_hole = Hole {
-                    name: hole.name,
-                    minutes: hole.minutes - 1,
-                    loss: hole.loss + compute_loss(hole.vented, losers, &suspicious),
-                    vented: hole.vented | (1 << index),
-                };
-
-                if vent_hole.loss
-                    < min_loss_for
-                        .get(&vent_hole.key())
-                        .copied()
-                        .unwrap_or(u32::MAX)
-                {
-                    heap.push(vent_hole);
-                    min_loss_for.insert(vent_hole.key(), vent_hole.loss);
-                }
+        if let Some(&(yours, your_valves)) = openings[i..]
+            .iter()
+            .find(|&&(_, your_valves)| my_valves & your_valves == 0)
+        {
+            if mine + yours > best_pair.0 {
+                best_pair = (mine + yours, my_valves, your_valves);
             }
         }
+    }
+    let (_, my_valves, your_valves) = best_pair;
+    Plan {
+        agents: vec![
+            ("you", network.openings(&best[&my_valves].states, 26)),
+            (
+                "the elephant",
+                network.openings(&best[&your_valves].states, 26),
+            ),
+        ],
+    }
+}
 
-        for &neighbor in neighbors[&hole.name].iter() {
-            let next_hole = Hole {
-                name: neighbor,
-                minutes: hole.minutes - 1,
-                loss: hole.loss + compute_loss(hole.vented, losers, &suspicious),
-                vented: hole.vented,
-            };
+#[aoc(day16, part1)]
+fn solo_vent(input: &[Valve]) -> u32 {
+    solo_plan(input).released()
+}
 
-            if next_hole.loss
-                < min_loss_for
-                    .get(&next_hole.key())
-                    .copied()
-                    .unwrap_or(u32::MAX)
-            {
-                heap.push(next_hole);
-                min_loss_for.insert(next_hole.key(), next_hole.loss);
-            }
-        }
-    }
-    total_possible_loss - min_loss
+#[aoc(day16, part1, explain)]
+fn explain_solo_vent(input: &[Valve]) -> Plan {
+    solo_plan(input)
 }
 
 #[aoc(day16, part2)]
 fn pair_vent(input: &[Valve]) -> u32 {
-    let minutes_left = 26;
-
-    let suspicious: Vec<_> = input
-        .iter()
-        .filter(|valve| valve.rate > 0)
-        .cloned()
-        .collect();
-
-    let total_possible_loss: u32 = suspicious
-        .iter()
-        .map(|valve| valve.rate * minutes_left as u32)
-        .sum();
-    let losers = suspicious.len();
-
-    let mut neighbors = HashMap::new();
-    for valve in input.iter() {
-        neighbors.insert(valve.name, valve.connections.clone());
-    }
-
-    let (my_start, your_start) = ((b'A', b'A'), (b'A', b'A'));
-    // we want the shortest path that visits all relievers in the order that minimizes loss
-    // loss = total flow not being released per tick
-    let mut heap = BinaryHeap::new();
-    let first_hole = DoubleHole {
-        me: Hole {
-            name: my_start,
-            minutes: minutes_left,
-            loss: 0,
-            vented: 0,
-        },
-        your_name: your_start,
-    };
-
-    heap.push(first_hole);
-
-    let mut min_loss_for = HashMap::new();
-    min_loss_for.insert(first_hole.key(), 0u32);
-    let mut min_loss = u32::MAX;
-
-    while let Some(hole) = heap.pop() {
-        if hole.me.minutes == 0 {
-            min_loss = min_loss.min(hole.me.loss);
-            break;
-        }
-        if hole.me.vented == (1 << losers) - 1 {
-            min_loss = min_loss.min(hole.me.loss);
-            break;
-        }
-
-        if hole.me.loss > min_loss_for.get(&hole.key()).copied().unwrap_or(u32::MAX) {
-            continue;
-        }
+    pair_plan(input).released()
+}
 
-        if let Some(index) = (0..losers).find(|&i| suspicious[i].name == hole.me.name) {
-            if hole.me.vented & (1 << index) == 0 {
-                if let Some(your_index) =
-                    (0..losers).find(|&i| suspicious[i].name == hole.your_name)
-                {
-                    if hole.me.vented & (1 << your_index) == 0 {
-                        let vent_hole = DoubleHole {
-                            me: Hole {
-                                name: hole.me.name,
-                                minutes: hole.me.minutes - 1,
-                                loss: hole.me.loss
-                                    + compute_loss(hole.me.vented, losers, &suspicious),
-                                vented: hole.me.vented | (1 << index) | (1 << your_index),
-                            },
-                            your_name: hole.your_name,
-                        };
+#[aoc(day16, part2, explain)]
+fn explain_pair_vent(input: &[Valve]) -> Plan {
+    pair_plan(input)
+}
 
-                        if vent_hole.me.loss
-                            < min_loss_for
-                                .get(&vent_hole.key())
-                                .copied()
-                                .unwrap_or(u32::MAX)
-                        {
-                            heap.push(vent_hole);
-                            min_loss_for.insert(vent_hole.key(), vent_hole.me.loss);
-                        }
-                    }
-                }
-                for &your_neighbor in neighbors[&hole.your_name].iter() {
-                    let vent_hole = DoubleHole {
-                        me: Hole {
-                            name: hole.me.name,
-                            minutes: hole.me.minutes - 1,
-                            loss: hole.me.loss + compute_loss(hole.me.vented, losers, &suspicious),
-                            vented: hole.me.vented | (1 << index),
-                        },
-                        your_name: your_neighbor,
-                    };
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-                    if vent_hole.me.loss
-                        < min_loss_for
-                            .get(&vent_hole.key())
-                            .copied()
-                            .unwrap_or(u32::MAX)
-                    {
-                        heap.push(vent_hole);
-                        min_loss_for.insert(vent_hole.key(), vent_hole.me.loss);
-                    }
-                }
-            }
-        }
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day16.txt");
 
-        for &neighbor in neighbors[&hole.me.name].iter() {
-            if let Some(your_index) = (0..losers).find(|&i| suspicious[i].name == hole.your_name) {
-                if hole.me.vented & (1 << your_index) == 0 {
-                    let vent_hole = DoubleHole {
-                        me: Hole {
-                            name: neighbor,
-                            minutes: hole.me.minutes - 1,
-                            loss: hole.me.loss + compute_loss(hole.me.vented, losers, &suspicious),
-                            vented: hole.me.vented | (1 << your_index),
-                        },
-                        your_name: hole.your_name,
-                    };
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(solo_vent(&input), 1651);
+    }
 
-                    if vent_hole.me.loss
-                        < min_loss_for
-                            .get(&vent_hole.key())
-                            .copied()
-                            .unwrap_or(u32::MAX)
-                    {
-                        heap.push(vent_hole);
-                        min_loss_for.insert(vent_hole.key(), vent_hole.me.loss);
-                    }
-                }
-            }
-            for &your_neighbor in neighbors[&hole.your_name].iter() {
-                let vent_hole = DoubleHole {
-                    me: Hole {
-                        name: neighbor,
-                        minutes: hole.me.minutes - 1,
-                        loss: hole.me.loss + compute_loss(hole.me.vented, losers, &suspicious),
-                        vented: hole.me.vented,
-                    },
-                    your_name: your_neighbor,
-                };
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(pair_vent(&input), 1707);
+    }
 
-                if vent_hole.me.loss
-                    < min_loss_for
-                        .get(&vent_hole.key())
-                        .copied()
-                        .unwrap_or(u32::MAX)
-                {
-                    heap.push(vent_hole);
-                    min_loss_for.insert(vent_hole.key(), vent_hole.me.loss);
-                }
-            }
-        }
+    #[test]
+    fn plan() {
+        let input = generator(EXAMPLE).unwrap();
+        let plan = solo_plan(&input);
+        let order: Vec<_> = plan.agents[0].1.iter().map(|o| o.name).collect();
+        assert_eq!(
+            order,
+            [
+                (b'D', b'D'),
+                (b'B', b'B'),
+                (b'J', b'J'),
+                (b'H', b'H'),
+                (b'E', b'E'),
+                (b'C', b'C')
+            ]
+        );
+        assert_eq!(plan.agents[0].1[0].minute, 2);
+
+        let plan = pair_plan(&input);
+        assert_eq!(plan.agents.len(), 2);
+        assert_eq!(plan.agents.iter().map(|(_, o)| o.len()).sum::<usize>(), 6);
     }
-    total_possible_loss - min_loss
 }
@@ -1,12 +1,42 @@
 use fxhash::FxHashMap;
 
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+    visualize::{Frame, Recorder},
+};
+
+#[aoc_generator(day17)]
+fn generator(input: &str) -> Result<Vec<i8>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(17, input);
+    if input.is_empty() {
+        return Err(ctx.error(input, "a jet pattern"));
+    }
+    input
+        .char_indices()
+        .map(|(i, c)| match c {
+            '<' => Ok(-1),
+            '>' => Ok(1),
+            _ => Err(ctx.error(&input[i..], "`<` or `>`")),
+        })
+        .collect()
+}
+
 #[aoc(day17, part1)]
-fn small(input: &[u8]) -> usize {
-    falling_simulation(input, 2022)
+fn small(input: &[i8]) -> usize {
+    falling_simulation(input, 2022, &mut Recorder::off())
 }
 #[aoc(day17, part2)]
-fn big(input: &[u8]) -> usize {
-    falling_simulation(input, 1_000_000_000_000)
+fn big(input: &[i8]) -> usize {
+    falling_simulation(input, 1_000_000_000_000, &mut Recorder::off())
+}
+
+/// Records a frame each time a rock comes to rest
+pub(crate) fn visualize(input: &str, part: u32, recorder: &mut Recorder) -> Result<(), ParseError> {
+    let blocks = if part == 2 { 1_000_000_000_000 } else { 2022 };
+    falling_simulation(&generator(input)?, blocks, recorder);
+    Ok(())
 }
 
 // Not rigorously chosen but seems to work enough for most input
@@ -62,24 +92,28 @@ fn freeze_polyomino(board: &mut [u8], polyomino: usize, x: i8, y: usize) -> usiz
     board.len() - y - 4 + height
 }
 
-#[allow(unused)]
-fn debug(board: &[u8]) {
-    println!("+-------+");
-    for i in 0..board.len() {
-        let j = board.len() - 1 - i;
-        print!("|");
-        for k in 0..7 {
-            let l = 6 - k;
-            if board[j] & (1 << l) != 0 {
-                print!("#");
-            } else {
-                print!(" ");
+/// The top of the chamber, down to the floor once it's in view
+fn draw(board: &[u8], height: usize) -> Frame {
+    const ROWS: usize = 40;
+    let top = height as isize + 3;
+    let mut frame = Frame::new(9, ROWS, b' ');
+    for (i, row) in frame.rows_mut().enumerate() {
+        let y = top - 1 - i as isize;
+        if y >= 0 {
+            row[0] = b'|';
+            for (k, cell) in row[1..8].iter_mut().enumerate() {
+                *cell = if board[y as usize] & (1 << (6 - k)) != 0 {
+                    b'#'
+                } else {
+                    b'.'
+                };
             }
+            row[8] = b'|';
+        } else if y == -1 {
+            row.copy_from_slice(b"+-------+");
         }
-        print!("|");
-        println!();
     }
-    println!("+-------+");
+    frame
 }
 
 type Key = ([u8; MAX_DEPTH_KEY], usize, usize);
@@ -104,7 +138,7 @@ fn cycle_growth_length(fingerprints: &FxHashMap<Key, (Key, usize)>, start: &Key)
     (total_growth, i)
 }
 
-fn falling_simulation(input: &[u8], blocks: usize) -> usize {
+fn falling_simulation(input: &[i8], blocks: usize, recorder: &mut Recorder) -> usize {
     // Earlier elements are lower in the board
     let mut board = vec![0; 7];
 
@@ -136,14 +170,9 @@ fn falling_simulation(input: &[u8], blocks: usize) -> usize {
         });
 
         loop {
-            let motion = input[step];
+            let delta = input[step];
             step += 1;
             step %= input.len();
-            let delta = match motion {
-                b'<' => -1,
-                b'>' => 1,
-                _ => unreachable!("bad byte in input"),
-            };
             if !check_collision(&board, polyomino, x + delta, y) {
                 x += delta;
             }
@@ -160,6 +189,7 @@ fn falling_simulation(input: &[u8], blocks: usize) -> usize {
                 polyomino += 1;
                 polyomino %= 5;
                 board.resize(height + 3 + 4, 0);
+                recorder.record(|| draw(&board, height));
                 let new_state = compute_key(&board, polyomino, step);
                 initial_state.map(|state| fingerprints.insert(state, (new_state, growth)));
                 break;
@@ -169,3 +199,22 @@ fn falling_simulation(input: &[u8], blocks: usize) -> usize {
     }
     height + bonus_height
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day17.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(small(&input), 3068);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(big(&input), 1514285714288);
+    }
+}
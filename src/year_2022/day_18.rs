@@ -1,60 +1,53 @@
-use std::fmt::Debug;
+use crate::{
+    error::{ParseContext, ParseError},
+    geometry::Point3,
+    input::normalize,
+};
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug)]
-struct Cube {
-    x: u8,
-    y: u8,
-    z: u8,
-}
-
-const DELTAS: [(i8, i8, i8); 6] = [
-    (1, 0, 0),
-    (-1, 0, 0),
-    (0, 1, 0),
-    (0, -1, 0),
-    (0, 0, 1),
-    (0, 0, -1),
-];
+type Cube = Point3<u8>;
 
 // may be adjusted if bigger inputs are given
 const BIT_WIDTH: usize = 5;
 const MAX_INDEX: u8 = 24;
 
-impl Cube {
-    fn index(&self) -> usize {
-        self.x as usize | (self.y as usize) << (BIT_WIDTH) | (self.z as usize) << (BIT_WIDTH * 2)
-    }
+fn index(cube: Cube) -> usize {
+    cube.x as usize | (cube.y as usize) << (BIT_WIDTH) | (cube.z as usize) << (BIT_WIDTH * 2)
+}
 
-    fn from_index(idx: usize) -> Cube {
-        let mask = (1 << BIT_WIDTH) - 1;
-        Cube {
-            x: (idx & mask) as u8,
-            y: (idx >> BIT_WIDTH & mask) as u8,
-            z: (idx >> (2 * BIT_WIDTH) & mask) as u8,
-        }
+fn from_index(idx: usize) -> Cube {
+    let mask = (1 << BIT_WIDTH) - 1;
+    Cube {
+        x: (idx & mask) as u8,
+        y: (idx >> BIT_WIDTH & mask) as u8,
+        z: (idx >> (2 * BIT_WIDTH) & mask) as u8,
     }
+}
 
-    fn add(&self, (dx, dy, dz): (i8, i8, i8)) -> Cube {
-        Cube {
-            x: self.x.saturating_add_signed(dx).min(MAX_INDEX),
-            y: self.y.saturating_add_signed(dy).min(MAX_INDEX),
-            z: self.z.saturating_add_signed(dz).min(MAX_INDEX),
-        }
+fn step(cube: Cube, delta: Point3<i8>) -> Cube {
+    Cube {
+        x: cube.x.saturating_add_signed(delta.x).min(MAX_INDEX),
+        y: cube.y.saturating_add_signed(delta.y).min(MAX_INDEX),
+        z: cube.z.saturating_add_signed(delta.z).min(MAX_INDEX),
     }
 }
 
 #[aoc_generator(day18)]
-fn generator(input: &str) -> Vec<Cube> {
+fn generator(input: &str) -> Result<Vec<Cube>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(18, input);
+    let coordinate = |n: &str| match ctx.number::<u8>(n)? {
+        // leave room for the shift below, and for air on the far side
+        c if c < MAX_INDEX - 1 => Ok(c),
+        _ => Err(ctx.error(n, format!("a coordinate between 0 and {}", MAX_INDEX - 2))),
+    };
     input
         .lines()
         .map(|line| {
-            let mut coords = line.split(',').map(|n| n.parse().unwrap());
-            Cube {
-                x: coords.next().unwrap(),
-                y: coords.next().unwrap(),
-                z: coords.next().unwrap(),
-            }
-            .add((1, 1, 1)) // shift by 1 so any border checks around zeros don't underflow
+            let (x, rest) = ctx.split_once(line, ",")?;
+            let (y, z) = ctx.split_once(rest, ",")?;
+            let cube = Cube::new(coordinate(x)?, coordinate(y)?, coordinate(z)?);
+            // shift by 1 so any border checks around zeros don't underflow
+            Ok(step(cube, Point3::new(1, 1, 1)))
         })
         .collect()
 }
@@ -95,7 +88,7 @@ fn external_surface_area(cubes: &[Cube]) -> u32 {
 fn flood_fill(cubes: &[Cube], part2: bool) -> u32 {
     let mut space = vec![Point::air(part2); 1 << (BIT_WIDTH * 3)];
     for cube in cubes {
-        space[cube.index()] = Point::lava(part2);
+        space[index(*cube)] = Point::lava(part2);
     }
 
     let mut surface_area = 0;
@@ -118,8 +111,8 @@ fn flood_fill_loop(space: &mut [Point], stack: &mut Vec<usize>, surface_area: &m
             continue;
         }
         space[idx] = Point::Visited;
-        for delta in DELTAS {
-            let new = Cube::from_index(idx).add(delta).index();
+        for delta in Point3::faces() {
+            let new = index(step(from_index(idx), delta));
             match space[new] {
                 Point::Blocking => *surface_area += 1,
                 Point::Visitable => stack.push(new),
@@ -128,3 +121,22 @@ fn flood_fill_loop(space: &mut [Point], stack: &mut Vec<usize>, surface_area: &m
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day18.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(total_surface_area(&input), 64);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(external_surface_area(&input), 58);
+    }
+}
@@ -1,5 +1,5 @@
 use std::{
-    collections::{BinaryHeap, HashMap},
+    fmt,
     ops::{Add, Div, Index, Mul, Sub},
     str::FromStr,
 };
@@ -9,6 +9,12 @@ use num::{
     Zero,
 };
 
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+    search::{self, Path},
+};
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 struct Blueprint {
     id: u16,
@@ -23,23 +29,22 @@ impl Blueprint {
     }
 }
 
-impl FromStr for Blueprint {
-    type Err = ();
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
+impl Blueprint {
+    fn parse(ctx: &ParseContext, s: &str) -> Result<Self, ParseError> {
         // Here's a messed up long statement
-        let (
-            id,
-            ore_cost,
-            clay_cost,
-            obsidian_cost_0,
-            obsidian_cost_1,
-            geode_cost_0,
-            geode_cost_1
-        ) = scan_fmt::scan_fmt!(s,
-            "Blueprint {}: Each ore robot costs {} ore. Each clay robot costs {} ore. Each obsidian robot costs {} ore and {} clay. Each geode robot costs {} ore and {} obsidian.",
-            u16, u16, u16, u16, u16, u16, u16
-        ).unwrap();
+        let mut rest = ctx.strip_prefix(s, "Blueprint ")?;
+        let mut field = |delimiter| -> Result<u16, ParseError> {
+            let (n, tail) = ctx.split_once(rest, delimiter)?;
+            rest = tail;
+            ctx.number(n)
+        };
+        let id = field(": Each ore robot costs ")?;
+        let ore_cost = field(" ore. Each clay robot costs ")?;
+        let clay_cost = field(" ore. Each obsidian robot costs ")?;
+        let obsidian_cost_0 = field(" ore and ")?;
+        let obsidian_cost_1 = field(" clay. Each geode robot costs ")?;
+        let geode_cost_0 = field(" ore and ")?;
+        let geode_cost_1 = field(" obsidian.")?;
 
         Ok(Blueprint {
             id,
@@ -60,9 +65,22 @@ impl FromStr for Blueprint {
     }
 }
 
+impl FromStr for Blueprint {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Blueprint::parse(&ParseContext::new(19, s), s)
+    }
+}
+
 #[aoc_generator(day19)]
-fn generator(input: &str) -> Vec<Blueprint> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
+fn generator(input: &str) -> Result<Vec<Blueprint>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(19, input);
+    input
+        .lines()
+        .map(|line| Blueprint::parse(&ctx, line))
+        .collect()
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
@@ -83,6 +101,17 @@ enum Material {
 }
 use Material::*;
 
+impl fmt::Display for Material {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Ore => "ore",
+            Clay => "clay",
+            Obsidian => "obsidian",
+            Geode => "geode",
+        })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
 struct MatVec<T: Copy>([T; 4]);
 
@@ -222,95 +251,213 @@ impl State {
     }
 }
 
-impl Ord for State {
-    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-        self.minutes
-            .cmp(&other.minutes)
-            .then_with(|| self.materials[Geode].cmp(&other.materials[Geode]))
-            .then_with(|| (self.robots, self.materials).cmp(&(other.robots, other.materials)))
-    }
+/// Geodes forgone while the minutes left run down from `from` to `to`, compared to
+/// building a geode robot every single minute.
+fn forgone(from: u16, to: u16) -> u16 {
+    let triangle = |minutes: u16| minutes * minutes.saturating_sub(1) / 2;
+    triangle(from) - triangle(to)
 }
 
-impl PartialOrd for State {
-    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(other))
+impl State {
+    /// A lower bound on the geodes this state must still forgo: nothing can be done about
+    /// the minutes before there's enough obsidian for a geode robot, even if an obsidian
+    /// robot were built every minute.
+    fn forgone_at_least(&self, blueprint: &Blueprint) -> u16 {
+        let needed = blueprint.costs_per_robot[Geode][Obsidian];
+        let mut obsidian = self.materials[Obsidian];
+        let mut robots = self.robots[Obsidian];
+        let mut minutes = self.minutes;
+        while obsidian < needed && minutes > 0 {
+            obsidian += robots;
+            robots += 1;
+            minutes -= 1;
+        }
+        forgone(self.minutes, minutes)
     }
-}
 
-fn sum_for(blueprints: &[Blueprint], minutes: u16) -> Vec<(u16, u16)> {
-    let mut counts = vec![];
-    let mut visited = 0;
-    for blueprint in blueprints {
-        let mut heap = BinaryHeap::new();
+    fn successors(&self, blueprint: &Blueprint) -> Vec<(State, u16)> {
+        let mut next = vec![];
+        // no further options
+        if self.minutes == 0 {
+            return next;
+        }
 
-        let initial_state = State {
-            materials: MatVec::new(0, 0, 0, 0),
-            robots: MatVec::new(1, 0, 0, 0),
-            minutes,
-        };
-        heap.push(initial_state);
+        // each state has choices
+        // these are then pruned depending on time remaining, demand for robots,
+        // and a heuristic upper bound for geodes produced
+        let waits = self
+            // would buying the robot be useless since the extra material can't be used?
+            .leftover_yields(blueprint)
+            // would buying the robot be useless since you can already buy one of every robot per minute?
+            .map2(self.enough_robots(blueprint), |a, b| !a && !b)
+            // ... but there's never enough geode machines
+            .map2(MatVec::new(false, false, false, true), |a, b| a || b)
+            .map2(self.minutes_until_build(blueprint), |a, b| a.then_some(b))
+            // would buying the robot be useless since the time runs out before it collects anything?
+            // this includes cases where you will never wait enough time (production = 0)
+            .map(|min| min.filter(|&m| m < self.minutes - 1));
+
+        // choice: wait until you can build a certain robot, then build it
+        for mat in [Ore, Clay, Obsidian, Geode] {
+            if let Some(wait) = waits[mat] {
+                let built = self.process_robots(wait + 1).build(mat, blueprint);
+                let mut cost = forgone(self.minutes, built.minutes);
+                if mat == Geode {
+                    // it collects a geode every minute left
+                    cost -= built.minutes;
+                }
+                next.push((built, cost));
+            }
+        }
+        // or simply do nothing until the end, lol
+        next.push((self.process_robots(self.minutes), forgone(self.minutes, 0)));
+        next
+    }
+}
 
-        let mut scores = HashMap::new();
-        let mut max_geodes = vec![0; minutes as usize + 1];
+/// The way to crack the most geodes with a blueprint. Geodes are only counted once the
+/// time runs out, so the answer is in the last state.
+fn best_plan(blueprint: &Blueprint, minutes: u16) -> Path<State, u16> {
+    let initial_state = State {
+        materials: MatVec::new(0, 0, 0, 0),
+        robots: MatVec::new(1, 0, 0, 0),
+        minutes,
+    };
+    // maximizing geodes is the same as minimizing the geodes that were missed out on
+    search::astar(
+        initial_state,
+        |state| state.successors(blueprint),
+        |state| state.forgone_at_least(blueprint),
+        |state| state.minutes == 0,
+    )
+    .expect("doing nothing is always an option")
+}
 
-        while let Some(state) = heap.pop() {
-            // no cycles exist, but it's likely for two branches to converge
-            if scores.contains_key(&state) {
-                continue;
-            }
-            scores.insert(state, state.materials[Geode]);
-            max_geodes[state.minutes as usize] =
-                max_geodes[state.minutes as usize].max(state.materials[Geode]);
+/// The robots to build with a blueprint, and when
+#[derive(Debug)]
+struct BuildOrder {
+    id: u16,
+    geodes: u16,
+    /// The minute spent building each robot, counting from 1
+    builds: Vec<(u16, Material)>,
+}
 
-            // no further options
-            if state.minutes == 0 {
-                continue;
-            }
-            if max_geodes[state.minutes as usize] >= state.materials[Geode] + 2 {
-                continue;
-            }
-            visited += 1;
-
-            // each state has choices
-            // these are then pruned depending on time remaining, demand for robots,
-            // and a heuristic upper bound for geodes produced
-            let waits = state
-                // would buying the robot be useless since the extra material can't be used?
-                .leftover_yields(blueprint)
-                // would buying the robot be useless since you can already buy one of every robot per minute?
-                .map2(state.enough_robots(blueprint), |a, b| !a && !b)
-                // ... but there's never enough geode machines
-                .map2(MatVec::new(false, false, false, true), |a, b| a || b)
-                .map2(state.minutes_until_build(blueprint), |a, b| a.then_some(b))
-                // would buying the robot be useless since the time runs out before you can reach it?
-                // this includes cases where you will never wait enough time (production = 0)
-                .map(|min| min.filter(|&m| m < state.minutes));
-
-            // choice: wait until you can build a certain robot, then build it
-            [Ore, Clay, Obsidian, Geode].iter().for_each(|&mat| {
-                if let Some(wait) = waits[mat] {
-                    heap.push(state.process_robots(wait + 1).build(mat, blueprint));
-                }
-            });
-            // or simply do nothing until the end, lol
-            heap.push(state.process_robots(state.minutes));
+impl BuildOrder {
+    fn new(blueprint: &Blueprint, minutes: u16) -> BuildOrder {
+        let plan = best_plan(blueprint, minutes);
+        let builds = plan
+            .states
+            .windows(2)
+            .filter_map(|pair| {
+                let built = [Ore, Clay, Obsidian, Geode]
+                    .into_iter()
+                    .find(|&mat| pair[1].robots[mat] > pair[0].robots[mat])?;
+                Some((minutes - pair[1].minutes, built))
+            })
+            .collect();
+        BuildOrder {
+            id: blueprint.id,
+            geodes: plan.states.last().unwrap().materials[Geode],
+            builds,
         }
-        counts.push((blueprint.id, max_geodes.into_iter().max().unwrap_or(0)));
     }
+}
 
-    println!("visited {visited} states");
-    counts
+impl fmt::Display for BuildOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "blueprint {}: {} geodes", self.id, self.geodes)?;
+        for (minute, robot) in &self.builds {
+            let article = if matches!(robot, Ore | Obsidian) {
+                "an"
+            } else {
+                "a"
+            };
+            write!(f, "\n  minute {minute}: build {article} {robot} robot")?;
+        }
+        Ok(())
+    }
+}
+
+fn build_orders(blueprints: &[Blueprint], minutes: u16) -> Vec<BuildOrder> {
+    blueprints
+        .iter()
+        .map(|blueprint| BuildOrder::new(blueprint, minutes))
+        .collect()
+}
+
+fn explain(orders: &[BuildOrder]) -> String {
+    orders
+        .iter()
+        .map(BuildOrder::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
 }
 
 #[aoc(day19, part1)]
 fn quick_but_wide(blueprints: &[Blueprint]) -> u16 {
-    sum_for(blueprints, 24).iter().map(|(a, b)| a * b).sum()
+    build_orders(blueprints, 24)
+        .iter()
+        .map(|order| order.id * order.geodes)
+        .sum()
+}
+
+#[aoc(day19, part1, explain)]
+fn explain_quick_but_wide(blueprints: &[Blueprint]) -> String {
+    explain(&build_orders(blueprints, 24))
 }
 
 #[aoc(day19, part2)]
 fn slow_but_narrow(blueprints: &[Blueprint]) -> u16 {
-    sum_for(&blueprints[0..3], 32)
+    build_orders(&blueprints[..blueprints.len().min(3)], 32)
         .iter()
-        .map(|(_, b)| b)
+        .map(|order| order.geodes)
         .product()
 }
+
+#[aoc(day19, part2, explain)]
+fn explain_slow_but_narrow(blueprints: &[Blueprint]) -> String {
+    explain(&build_orders(&blueprints[..blueprints.len().min(3)], 32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day19.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(quick_but_wide(&input), 33);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(slow_but_narrow(&input), 56 * 62);
+    }
+
+    #[test]
+    fn build_order() {
+        let input = generator(EXAMPLE).unwrap();
+        let order = BuildOrder::new(&input[0], 24);
+        assert_eq!(order.geodes, 9);
+        // replaying the builds cracks as many geodes
+        let mut state = State {
+            materials: MatVec::new(0, 0, 0, 0),
+            robots: MatVec::new(1, 0, 0, 0),
+            minutes: 24,
+        };
+        for &(minute, robot) in &order.builds {
+            state = state.process_robots(state.minutes - (24 - minute) - 1);
+            let cost = input[0].costs_per_robot[robot];
+            assert!(state
+                .materials
+                .map2(cost, |have, need| have >= need)
+                .into_iter()
+                .all(|b| b));
+            state = state.process_robots(1).build(robot, &input[0]);
+        }
+        assert_eq!(state.process_robots(state.minutes).materials[Geode], 9);
+    }
+}
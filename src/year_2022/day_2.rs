@@ -1,10 +1,26 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
+#[aoc_generator(day2)]
+fn generator(input: &str) -> Result<Vec<(u8, u8)>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(2, input);
+    input
+        .lines()
+        .map(|line| match line.as_bytes() {
+            &[opponent @ b'A'..=b'C', b' ', you @ b'X'..=b'Z'] => Ok((opponent - b'A', you - b'X')),
+            _ => Err(ctx.error(line, "`<A|B|C> <X|Y|Z>`")),
+        })
+        .collect()
+}
+
 #[aoc(day2, part1)]
-fn action_score(input: &[u8]) -> u32 {
+fn action_score(input: &[(u8, u8)]) -> u32 {
     input
-        .array_chunks::<4>()
-        .map(|&[opponent, _, you, _]| {
-            let opponent = opponent - b'A';
-            let you = you - b'X';
+        .iter()
+        .map(|&(opponent, you)| {
             let choice_score = you + 1;
             let win_score = (you + 3 - opponent + 1) % 3 * 3;
             (choice_score + win_score) as u32
@@ -12,12 +28,10 @@ fn action_score(input: &[u8]) -> u32 {
         .sum()
 }
 #[aoc(day2, part2)]
-fn outcome_score(input: &[u8]) -> u32 {
+fn outcome_score(input: &[(u8, u8)]) -> u32 {
     input
-        .array_chunks::<4>()
-        .map(|&[opponent, _, outcome, _]| {
-            let opponent = opponent - b'A';
-            let outcome = outcome - b'X';
+        .iter()
+        .map(|&(opponent, outcome)| {
             let win_score = outcome * 3;
             let you = (opponent + outcome + 2) % 3;
             let choice_score = you + 1;
@@ -25,3 +39,22 @@ fn outcome_score(input: &[u8]) -> u32 {
         })
         .sum()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day2.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(action_score(&input), 15);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(outcome_score(&input), 12);
+    }
+}
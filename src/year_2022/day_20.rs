@@ -1,6 +1,13 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 #[aoc_generator(day20)]
-fn generator(input: &str) -> Vec<i64> {
-    input.lines().map(|l| l.parse().unwrap()).collect()
+fn generator(input: &str) -> Result<Vec<i64>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(20, input);
+    input.lines().map(|l| ctx.number(l)).collect()
 }
 
 fn mix(ints: &[i64], mul: i64, times: usize) -> i64 {
@@ -32,3 +39,22 @@ fn smol_decryption(input: &[i64]) -> i64 {
 fn big_decryption(input: &[i64]) -> i64 {
     mix(input, 811_589_153, 10)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day20.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(smol_decryption(&input), 3);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(big_decryption(&input), 1623178306);
+    }
+}
@@ -1,8 +1,11 @@
-use std::array;
-
 use fxhash::FxHashMap;
 use num::{Rational64, Zero};
 
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 type Name = [u8; 4];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -68,29 +71,37 @@ impl Monkey {
     }
 }
 
+fn parse_name(ctx: &ParseContext, name: &str) -> Result<Name, ParseError> {
+    name.as_bytes()
+        .try_into()
+        .map_err(|_| ctx.error(name, "a four-letter monkey name"))
+}
+
 #[aoc_generator(day21)]
-fn generator(input: &str) -> Vec<(Name, Monkey)> {
+fn generator(input: &str) -> Result<Vec<(Name, Monkey)>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(21, input);
     input
         .lines()
         .map(|line| {
-            let b = line.as_bytes();
-            let name = array::from_fn(|i| b[i]);
-            let monkey = if b.len() == "abcd: efgh + ijkl".len() {
-                let x = array::from_fn(|i| b[i + 6]);
-                let y = array::from_fn(|i| b[i + 13]);
-                match b[11] {
-                    b'+' => Monkey::Add(x, y),
-                    b'-' => Monkey::Sub(x, y),
-                    b'*' => Monkey::Mul(x, y),
-                    b'/' => Monkey::Div(x, y),
-                    _ => unreachable!("bad monkey"),
+            let (name, job) = ctx.split_once(line, ": ")?;
+            let name = parse_name(&ctx, name)?;
+            let monkey = match job.split(' ').collect::<Vec<_>>()[..] {
+                [x, op, y] => {
+                    let x = parse_name(&ctx, x)?;
+                    let y = parse_name(&ctx, y)?;
+                    match op {
+                        "+" => Monkey::Add(x, y),
+                        "-" => Monkey::Sub(x, y),
+                        "*" => Monkey::Mul(x, y),
+                        "/" => Monkey::Div(x, y),
+                        _ => return Err(ctx.error(op, "one of `+`, `-`, `*` or `/`")),
+                    }
                 }
-            } else {
-                let n = std::str::from_utf8(&b[6..]).unwrap().parse().unwrap();
-                Monkey::Const(n)
+                _ => Monkey::Const(ctx.number(job)?),
             };
 
-            (name, monkey)
+            Ok((name, monkey))
         })
         .collect()
 }
@@ -131,3 +142,22 @@ fn solve(input: &[(Name, Monkey)]) -> i64 {
     }
     rhs.to_integer()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day21.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(eval(&input), 152);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(solve(&input), 301);
+    }
+}
@@ -1,10 +1,16 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    grid::Grid,
+    input::normalize,
+};
+
 const DIRECTIONS: [(isize, isize); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
 
-fn get_value_or(flat_map: &[Vec<u8>], v: usize, w: usize) -> u8 {
-    *flat_map.get(v).and_then(|row| row.get(w)).unwrap_or(&b' ')
+fn get_value_or(flat_map: &Grid<u8>, v: usize, w: usize) -> u8 {
+    *flat_map.get((w, v)).unwrap_or(&b' ')
 }
 
-fn flat_wrap(flat_map: &[Vec<u8>], (v, w, direction): State) -> State {
+fn flat_wrap(flat_map: &Grid<u8>, (v, w, direction): State) -> State {
     let mut v = v;
     let mut w = w;
     let (offset_v, offset_w) = DIRECTIONS[direction];
@@ -20,53 +26,67 @@ fn flat_wrap(flat_map: &[Vec<u8>], (v, w, direction): State) -> State {
     (v, w, direction)
 }
 
-const CUBE_SIZE: usize = 50;
-
 type State = (usize, usize, usize);
 
-fn cube_wrap(_: &[Vec<u8>], (v, w, direction): State) -> State {
-    let (macro_v, q_w, new_dir) = match (v / CUBE_SIZE, w / CUBE_SIZE, direction) {
-        // hard-coded transitions, woooo
-        (0, 1, 0) => (3, 0, 1),
-        (0, 1, 3) => (2, 0, 1),
-        (0, 2, 0) => (3, 0, 0),
-        (0, 2, 1) => (2, 1, 3),
-        (0, 2, 2) => (1, 1, 3),
-        (1, 1, 1) => (0, 2, 0),
-        (1, 1, 3) => (2, 0, 2),
-        (2, 0, 0) => (1, 1, 1),
-        (2, 0, 3) => (0, 1, 1),
-        (2, 1, 1) => (0, 2, 3),
-        (2, 1, 2) => (3, 0, 3),
-        (3, 0, 1) => (2, 1, 0),
-        (3, 0, 2) => (0, 2, 2),
-        (3, 0, 3) => (0, 1, 2),
-        _ => unreachable!(),
-    };
-    let (offset_v, offset_w) = (v % CUBE_SIZE, w % CUBE_SIZE);
+/// A face of the unfolded cube (in units of the cube size) and a direction
+type Edge = (usize, usize, usize);
+
+/// The way a particular map folds into a cube
+struct CubeNet {
+    size: usize,
+    transitions: &'static [(Edge, Edge)],
+}
+
+// hard-coded transitions, woooo
+const INPUT_NET: CubeNet = CubeNet {
+    size: 50,
+    transitions: &[
+        ((0, 1, 0), (3, 0, 1)),
+        ((0, 1, 3), (2, 0, 1)),
+        ((0, 2, 0), (3, 0, 0)),
+        ((0, 2, 1), (2, 1, 3)),
+        ((0, 2, 2), (1, 1, 3)),
+        ((1, 1, 1), (0, 2, 0)),
+        ((1, 1, 3), (2, 0, 2)),
+        ((2, 0, 0), (1, 1, 1)),
+        ((2, 0, 3), (0, 1, 1)),
+        ((2, 1, 1), (0, 2, 3)),
+        ((2, 1, 2), (3, 0, 3)),
+        ((3, 0, 1), (2, 1, 0)),
+        ((3, 0, 2), (0, 2, 2)),
+        ((3, 0, 3), (0, 1, 2)),
+    ],
+};
+
+fn cube_wrap(net: &CubeNet, (v, w, direction): State) -> State {
+    let size = net.size;
+    let (macro_v, q_w, new_dir) = net
+        .transitions
+        .iter()
+        .find(|&&(edge, _)| edge == (v / size, w / size, direction))
+        .map(|&(_, target)| target)
+        .expect("no transition for this edge of the cube");
+    // position along the edge, clockwise
+    let (offset_v, offset_w) = (v % size, w % size);
     let i = match direction {
-        0 => offset_v,
-        1 => offset_w,
-        2 => CUBE_SIZE - 1 - offset_v,
-        3 => CUBE_SIZE - 1 - offset_w,
+        0 => offset_w,
+        1 => offset_v,
+        2 => size - 1 - offset_w,
+        3 => size - 1 - offset_v,
         _ => unreachable!(),
     };
     let (macro_w, new_w) = [
-        (CUBE_SIZE - 1, i),
+        (size - 1, i),
         (i, 0),
-        (0, CUBE_SIZE - 1 - i),
-        (CUBE_SIZE - 1 - i, CUBE_SIZE - 1),
+        (0, size - 1 - i),
+        (size - 1 - i, size - 1),
     ][new_dir];
-    (
-        macro_v * CUBE_SIZE + macro_w,
-        q_w * CUBE_SIZE + new_w,
-        new_dir,
-    )
+    (macro_v * size + macro_w, q_w * size + new_w, new_dir)
 }
 
-fn walk(flat_map: &[Vec<u8>], moves: &[Move], wrapper: fn(&[Vec<u8>], State) -> State) -> usize {
-    let (mut y, mut x, mut direction) = (0, 0, 1);
-    while flat_map[0][x] != b'.' {
+fn walk(flat_map: &Grid<u8>, moves: &[Move], wrapper: impl Fn(&Grid<u8>, State) -> State) -> usize {
+    let (mut y, mut x, mut direction) = (0usize, 0usize, 1);
+    while flat_map[(x, 0)] != b'.' {
         x += 1;
     }
     for movement in moves {
@@ -76,16 +96,13 @@ fn walk(flat_map: &[Vec<u8>], moves: &[Move], wrapper: fn(&[Vec<u8>], State) ->
             &Move::Move(amount) => {
                 for _ in 0..amount {
                     let (dr, dc) = DIRECTIONS[direction];
-                    match flat_map
-                        .get(y + dr as usize)
-                        .and_then(|row| row.get(x + dc as usize))
-                        .unwrap_or(&b' ')
-                    {
-                        b'.' => (y, x) = (y + dr as usize, x + dc as usize),
+                    let (ny, nx) = (y.wrapping_add_signed(dr), x.wrapping_add_signed(dc));
+                    match get_value_or(flat_map, ny, nx) {
+                        b'.' => (y, x) = (ny, nx),
                         b'#' => break,
                         b' ' => {
                             let (nr, nc, d) = wrapper(flat_map, (y, x, direction));
-                            if flat_map[nr][nc] == b'#' {
+                            if flat_map[(nc, nr)] == b'#' {
                                 break;
                             }
                             (y, x, direction) = (nr, nc, d);
@@ -106,43 +123,89 @@ enum Move {
 }
 
 #[aoc_generator(day22)]
-fn generator(input: &str) -> (Vec<Vec<u8>>, Vec<Move>) {
-    let (sprawled, instructions) = input.split_once("\n\n").unwrap();
+fn generator(input: &str) -> Result<(Grid<u8>, Vec<Move>), ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(22, input);
+    let (sprawled, instructions) = ctx.split_once(input, "\n\n")?;
 
     // only unfolded cubes allowed here
-    let flat_map: Vec<Vec<u8>> = sprawled
-        .lines()
-        .map(|line| line.as_bytes().to_vec())
-        .collect();
+    let flat_map = Grid::parse_padded(&ctx, sprawled, "one of ` `, `.` or `#`", b' ', |c| {
+        matches!(c, b' ' | b'.' | b'#').then_some(c)
+    })?;
+    if !flat_map.row(0).contains(&b'.') {
+        return Err(ctx.error(sprawled, "an open tile on the first row"));
+    }
 
     // hardcoded transitions
 
-    let moves = instructions
-        .split_inclusive(|c| c == 'L' || c == 'R')
-        .flat_map(|chunk| {
-            let mut moves = vec![];
-            if chunk.as_bytes()[chunk.len() - 1] == b'L' {
-                moves.push(Move::Move(chunk[..chunk.len() - 1].parse().unwrap()));
-                moves.push(Move::Left);
-            } else if chunk.as_bytes()[chunk.len() - 1] == b'R' {
-                moves.push(Move::Move(chunk[..chunk.len() - 1].parse().unwrap()));
-                moves.push(Move::Right);
-            } else {
-                moves.push(Move::Move(chunk.parse().unwrap()));
-            }
-            moves
-        })
-        .collect();
+    let mut moves = vec![];
+    for chunk in instructions.split_inclusive(|c| c == 'L' || c == 'R') {
+        if let Some(amount) = chunk.strip_suffix('L') {
+            moves.push(Move::Move(ctx.number(amount)?));
+            moves.push(Move::Left);
+        } else if let Some(amount) = chunk.strip_suffix('R') {
+            moves.push(Move::Move(ctx.number(amount)?));
+            moves.push(Move::Right);
+        } else {
+            moves.push(Move::Move(ctx.number(chunk)?));
+        }
+    }
 
-    (flat_map, moves)
+    Ok((flat_map, moves))
 }
 
 #[aoc(day22, part1)]
-fn toroidal(input: &(Vec<Vec<u8>>, Vec<Move>)) -> usize {
+fn toroidal(input: &(Grid<u8>, Vec<Move>)) -> usize {
     walk(&input.0, &input.1, flat_wrap)
 }
 
 #[aoc(day22, part2)]
-fn cube_moment(input: &(Vec<Vec<u8>>, Vec<Move>)) -> usize {
-    walk(&input.0, &input.1, cube_wrap)
+fn cube_moment(input: &(Grid<u8>, Vec<Move>)) -> usize {
+    walk(&input.0, &input.1, |_, state| cube_wrap(&INPUT_NET, state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day22.txt");
+
+    // The example folds differently from the full input
+    const EXAMPLE_NET: CubeNet = CubeNet {
+        size: 4,
+        transitions: &[
+            ((0, 2, 0), (1, 0, 2)),
+            ((0, 2, 1), (2, 3, 3)),
+            ((0, 2, 3), (1, 1, 2)),
+            ((1, 0, 0), (0, 2, 2)),
+            ((1, 0, 2), (2, 2, 0)),
+            ((1, 0, 3), (2, 3, 0)),
+            ((1, 1, 0), (0, 2, 1)),
+            ((1, 1, 2), (2, 2, 1)),
+            ((1, 2, 1), (2, 3, 2)),
+            ((2, 2, 2), (1, 0, 0)),
+            ((2, 2, 3), (1, 1, 0)),
+            ((2, 3, 0), (1, 2, 3)),
+            ((2, 3, 1), (0, 2, 3)),
+            ((2, 3, 2), (1, 0, 1)),
+        ],
+    };
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(toroidal(&input), 6032);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(
+            walk(&input.0, &input.1, |_, state| cube_wrap(
+                &EXAMPLE_NET,
+                state
+            )),
+            5031
+        );
+    }
 }
@@ -1,80 +1,96 @@
-use fxhash::{FxHashMap, FxHashSet};
+use crate::{
+    error::{ParseContext, ParseError},
+    geometry::{Aabb, Point2},
+    grid::{self, Grid, SparseGrid},
+    input::normalize,
+    visualize::{Frame, Recorder},
+};
 
 #[aoc_generator(day23)]
-fn generator(input: &str) -> Vec<(i32, i32)> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(y, line)| {
-            line.bytes()
-                .enumerate()
-                .filter_map(move |(x, b)| (b == b'#').then_some((x as i32, y as i32)))
-        })
-        .collect()
+fn generator(input: &str) -> Result<SparseGrid<()>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(23, input);
+    let grove = Grid::parse_padded(&ctx, input, "`#` or `.`", false, |b| match b {
+        b'#' => Some(true),
+        b'.' => Some(false),
+        _ => None,
+    })?;
+    Ok(grove
+        .iter()
+        .filter(|&(_, &elf)| elf)
+        .map(|((x, y), _)| (Point2::new(x as isize, y as isize), ()))
+        .collect())
 }
 
-fn simulate(input: &[(i32, i32)], limit: usize) -> (FxHashSet<(i32, i32)>, usize) {
-    let mut elves = FxHashSet::from_iter(input.iter().copied());
-    let mut swap_buffer = FxHashSet::default();
-    let mut contested_moves = FxHashMap::default();
+type Offset = (isize, isize);
+
+// The squares each proposal checks, followed by the one it moves to
+const PROPOSALS: [([Offset; 3], Offset); 4] = [
+    ([(-1, -1), (0, -1), (1, -1)], (0, -1)), // N
+    ([(-1, 1), (0, 1), (1, 1)], (0, 1)),     // S
+    ([(-1, -1), (-1, 0), (-1, 1)], (-1, 0)), // W
+    ([(1, -1), (1, 0), (1, 1)], (1, 0)),     // E
+];
+
+fn draw(elves: &SparseGrid<()>, view: Aabb<isize>) -> Frame {
+    let mut frame = Frame::new(view.width() as usize, view.height() as usize, b'.');
+    for elf in elves.positions() {
+        let offset = elf - view.min;
+        frame[(offset.x as usize, offset.y as usize)] = b'#';
+    }
+    frame
+}
+
+/// Runs rounds until no elf moves or the limit is reached, showing each round's result to
+/// `on_round` along with the initial positions
+fn simulate(
+    input: &SparseGrid<()>,
+    limit: usize,
+    mut on_round: impl FnMut(&SparseGrid<()>),
+) -> (SparseGrid<()>, usize) {
+    let mut elves = input.clone();
+    on_round(&elves);
+    let mut swap_buffer = SparseGrid::new();
+    let mut contested_moves = SparseGrid::new();
     let mut step = 0;
     while step < limit {
         let mut still = true;
-        'elf_check: for &(x, y) in elves.iter() {
-            let neighbors = [
-                (x + 1, y + 1),
-                (x + 1, y - 1),
-                (x + 1, y),
-                (x - 1, y + 1),
-                (x - 1, y - 1),
-                (x - 1, y),
-                (x, y + 1),
-                (x, y - 1),
-            ];
-            if !neighbors.iter().any(|pos| elves.contains(pos)) {
-                swap_buffer.insert((x, y));
+        'elf_check: for elf in elves.positions() {
+            if !grid::neighbors8(elf).iter().any(|&pos| elves.contains(pos)) {
+                swap_buffer.insert(elf, ());
                 continue;
             }
-            let zones = [
-                [(x - 1, y - 1), (x, y - 1), (x + 1, y - 1)], // N
-                [(x - 1, y + 1), (x, y + 1), (x + 1, y + 1)], // S
-                [(x - 1, y - 1), (x - 1, y), (x - 1, y + 1)], // W
-                [(x + 1, y - 1), (x + 1, y), (x + 1, y + 1)], // E
-            ];
-            let targets = [
-                (x, y - 1), // N
-                (x, y + 1), // S
-                (x - 1, y), // W
-                (x + 1, y), // E
-            ];
             for i in 0..4 {
                 let index = (i + step) % 4;
-                let zone = zones[index];
-                if zone.iter().any(|pos| elves.contains(pos)) {
+                let (zone, target) = PROPOSALS[index];
+                if zone
+                    .iter()
+                    .any(|&delta| elves.contains(elf + Point2::from(delta)))
+                {
                     continue;
                 }
-                let target = targets[index];
+                let target = elf + Point2::from(target);
                 // The index is unique for each target-source pair
-                contested_moves.entry(target).or_insert([None; 4])[index] = Some((x, y));
+                contested_moves.entry(target).or_insert([None; 4])[index] = Some(elf);
                 continue 'elf_check;
             }
             // If we reach this point, all 4 movement attempts failed
-            swap_buffer.insert((x, y));
+            swap_buffer.insert(elf, ());
         }
-        for (&target, &attempts) in contested_moves.iter() {
+        for (target, &attempts) in contested_moves.iter() {
             match attempts {
                 [Some(_), None, None, None]
                 | [None, Some(_), None, None]
                 | [None, None, Some(_), None]
                 | [None, None, None, Some(_)] => {
-                    swap_buffer.insert(target);
+                    swap_buffer.insert(target, ());
                     // successful move; therefore keep simulating
                     still = false;
                 }
                 _ => {
                     attempts.into_iter().for_each(|attempt| {
                         if let Some(pos) = attempt {
-                            swap_buffer.insert(pos);
+                            swap_buffer.insert(pos, ());
                         }
                     });
                 }
@@ -84,6 +100,7 @@ fn simulate(input: &[(i32, i32)], limit: usize) -> (FxHashSet<(i32, i32)>, usize
         std::mem::swap(&mut elves, &mut swap_buffer);
         swap_buffer.clear();
         contested_moves.clear();
+        on_round(&elves);
         // no more movement, we can halt
         if still {
             break;
@@ -94,18 +111,66 @@ fn simulate(input: &[(i32, i32)], limit: usize) -> (FxHashSet<(i32, i32)>, usize
 }
 
 #[aoc(day23, part1)]
-fn aabb(input: &[(i32, i32)]) -> i32 {
-    let (elves, _) = simulate(input, 10);
+fn aabb(input: &SparseGrid<()>) -> isize {
+    let (elves, _) = simulate(input, 10, |_| ());
     // largest bounding box containing all elves
-    let x_min = elves.iter().map(|&(x, _)| x).min().unwrap_or(0);
-    let x_max = elves.iter().map(|&(x, _)| x).max().unwrap_or(0);
-    let y_min = elves.iter().map(|&(_, y)| y).min().unwrap_or(0);
-    let y_max = elves.iter().map(|&(_, y)| y).max().unwrap_or(0);
-    (x_max - x_min + 1) * (y_max - y_min + 1) - elves.len() as i32
+    let area = elves.bounds().map_or(0, |bounds| bounds.area());
+    area - elves.len() as isize
 }
 
 #[aoc(day23, part2)]
-fn halt(input: &[(i32, i32)]) -> usize {
-    let (_, steps) = simulate(input, usize::MAX);
+fn halt(input: &SparseGrid<()>) -> usize {
+    let (_, steps) = simulate(input, usize::MAX, |_| ());
     steps + 1
 }
+
+/// Records a frame for each round
+pub(crate) fn visualize(input: &str, part: u32, recorder: &mut Recorder) -> Result<(), ParseError> {
+    let elves = generator(input)?;
+    let limit = if part == 2 { usize::MAX } else { 10 };
+    // a dry run to find every square an elf stands on in any round, so none fall off the frame
+    let mut view = None;
+    simulate(&elves, limit, |elves| {
+        let corners = view
+            .into_iter()
+            .flat_map(|view: Aabb<isize>| [view.min, view.max]);
+        view = Aabb::from_points(corners.chain(elves.positions()));
+    });
+    if let Some(view) = view {
+        simulate(&elves, limit, |elves| recorder.record(|| draw(elves, view)));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day23.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(aabb(&input), 110);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(halt(&input), 20);
+    }
+
+    #[test]
+    fn frames() {
+        let mut sink = crate::visualize::Ascii(Vec::new());
+        let mut recorder = Recorder::new(&mut sink, 1);
+        visualize(EXAMPLE, 2, &mut recorder).unwrap();
+        recorder.finish().unwrap();
+        let frames = String::from_utf8(sink.0).unwrap();
+        // no elf ever wanders out of the picture
+        for frame in frames.split_terminator("\n\n") {
+            assert_eq!(frame.matches('#').count(), 22);
+        }
+        assert_eq!(frames.split_terminator("\n\n").count(), 21);
+    }
+}
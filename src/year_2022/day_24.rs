@@ -1,7 +1,12 @@
-use std::collections::VecDeque;
+use std::{cmp::Ordering, fmt};
 
-use bit_vec::BitVec;
-use fxhash::FxHashSet;
+use crate::{
+    error::{ParseContext, ParseError},
+    grid::Grid,
+    input::normalize,
+    search,
+    visualize::{Frame, Recorder},
+};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 struct State {
@@ -14,157 +19,259 @@ struct State {
 struct Blizzards {
     width: usize,
     height: usize,
-    right: Vec<BitVec>,
-    left: Vec<BitVec>,
-    up: Vec<BitVec>,
-    down: Vec<BitVec>,
+    // where each kind of blizzard starts out, within the walls
+    right: Grid<bool>,
+    left: Grid<bool>,
+    up: Grid<bool>,
+    down: Grid<bool>,
 }
 
 impl Blizzards {
     fn blizzed(&self, state: State) -> bool {
-        state.y != 0
-            && state.y != self.height + 1
-            && (self.right[state.y - 1]
-                [(state.x as isize - state.step as isize).rem_euclid(self.width as isize) as usize]
-                || self.left[state.y - 1][((state.x + state.step) % self.width)]
-                || self.down[state.x][(state.y as isize - state.step as isize - 1)
-                    .rem_euclid(self.height as isize)
-                    as usize]
-                || self.up[state.x][(state.y + state.step - 1) % self.height])
+        if state.y == 0 || state.y == self.height + 1 {
+            return false;
+        }
+        // where a blizzard would have started to be here by now
+        let (x, y, step) = (state.x as isize, state.y as isize - 1, state.step as isize);
+        let (width, height) = (self.width as isize, self.height as isize);
+        let wrap =
+            |x: isize, y: isize| (x.rem_euclid(width) as usize, y.rem_euclid(height) as usize);
+        self.right[wrap(x - step, y)]
+            || self.left[wrap(x + step, y)]
+            || self.down[wrap(x, y - step)]
+            || self.up[wrap(x, y + step)]
     }
 
     fn walled(&self, state: State) -> bool {
         (state.x != 0 && state.y == 0) || (state.x != self.width - 1 && state.y == self.height + 1)
     }
 
-    fn check_and_add(&self, queue: &mut VecDeque<State>, state: State) {
-        if !self.blizzed(state) && !self.walled(state) {
-            queue.push_back(state);
-        }
+    /// The states one minute later, including waiting in place
+    fn moves(&self, state: State) -> impl Iterator<Item = State> + '_ {
+        let step = state.step + 1;
+        let (x, y) = (state.x, state.y);
+        [
+            (x < self.width - 1).then(|| (x + 1, y)),
+            (x > 0).then(|| (x - 1, y)),
+            (y < self.height + 1).then(|| (x, y + 1)),
+            (y > 0).then(|| (x, y - 1)),
+            Some((x, y)),
+        ]
+        .into_iter()
+        .flatten()
+        .map(move |(x, y)| State { step, x, y })
+        .filter(|&state| !self.blizzed(state) && !self.walled(state))
     }
 }
 
 #[aoc_generator(day24)]
-fn generator(input: &str) -> Blizzards {
-    let height = input.lines().count() - 2;
-    let width = input.len() / (height + 2) - 2;
-
-    let mut right = vec![BitVec::from_elem(width, false); height];
-    let mut left = vec![BitVec::from_elem(width, false); height];
-    let mut up = vec![BitVec::from_elem(height, false); width];
-    let mut down = vec![BitVec::from_elem(height, false); width];
-
-    for (y, line) in input.lines().enumerate() {
-        for (x, c) in line.bytes().enumerate() {
-            match c {
-                b'>' => right[y - 1].set(x - 1, true), // x >= 1
-                b'<' => left[y - 1].set(x - 1, true),
-                b'v' => down[x - 1].set(y - 1, true),
-                b'^' => up[x - 1].set(y - 1, true),
-                _ => (),
-            }
+fn generator(input: &str) -> Result<Blizzards, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(24, input);
+    let valley = Grid::parse(&ctx, input, "one of `#`, `.`, `>`, `<`, `v` or `^`", |c| {
+        matches!(c, b'#' | b'.' | b'>' | b'<' | b'v' | b'^').then_some(c)
+    })?;
+    if valley.height() < 3 || valley.width() < 3 {
+        return Err(ctx.error_after(input, "a valley surrounded by walls"));
+    }
+    let height = valley.height() - 2;
+    let width = valley.width() - 2;
+
+    let mut right = Grid::new(width, height, false);
+    let mut left = Grid::new(width, height, false);
+    let mut up = Grid::new(width, height, false);
+    let mut down = Grid::new(width, height, false);
+
+    for ((x, y), &c) in valley.iter() {
+        if c == b'#' || c == b'.' {
+            continue;
+        }
+        if !(1..=height).contains(&y) || !(1..=width).contains(&x) {
+            let line = input.lines().nth(y).unwrap_or(input);
+            return Err(ctx.error(&line[x..], "a blizzard inside the walls"));
         }
+        let blizzards = match c {
+            b'>' => &mut right,
+            b'<' => &mut left,
+            b'v' => &mut down,
+            _ => &mut up,
+        };
+        blizzards[(x - 1, y - 1)] = true;
     }
 
-    Blizzards {
+    Ok(Blizzards {
         width,
         height,
         left,
         right,
         down,
         up,
-    }
+    })
 }
 
-fn pathfind(blizzards: &Blizzards, initial_state: State, goal: (usize, usize)) -> State {
-    let mut visited = FxHashSet::default();
-    let mut queue = VecDeque::new();
-    queue.push_back(initial_state);
+/// Every state on the way through, one per minute
+#[derive(Debug)]
+struct Trip {
+    states: Vec<State>,
+}
 
-    while let Some(state) = queue.pop_front() {
-        if (state.x, state.y) == goal {
-            return state;
+impl Trip {
+    /// Goes to each goal in turn, starting from the entrance
+    fn new(blizzards: &Blizzards, goals: &[(usize, usize)]) -> Trip {
+        let mut states = vec![State {
+            step: 0,
+            x: 0,
+            y: 0,
+        }];
+        for &goal in goals {
+            let from = *states.last().unwrap();
+            let leg = search::bfs(
+                from,
+                |&state| blizzards.moves(state),
+                |state| (state.x, state.y) == goal,
+            )
+            .unwrap_or_else(|| {
+                panic!(
+                    "the blizzards block every path from {:?} to {goal:?}",
+                    (from.x, from.y)
+                )
+            });
+            states.extend(leg.states.into_iter().skip(1));
         }
+        Trip { states }
+    }
 
-        if visited.contains(&state) {
-            //}.modulo(cycle)) {
-            continue;
-        }
-        visited.insert(state); //.modulo(cycle));
+    fn minutes(&self) -> usize {
+        self.states.last().unwrap().step
+    }
+}
 
-        if state.x < blizzards.width - 1 {
-            let new = State {
-                step: state.step + 1,
-                x: state.x + 1,
-                ..state
-            };
-            blizzards.check_and_add(&mut queue, new)
-        }
-        if state.x > 0 {
-            let new = State {
-                step: state.step + 1,
-                x: state.x - 1,
-                ..state
+impl fmt::Display for Trip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} minutes", self.minutes())?;
+        for pair in self.states.windows(2) {
+            let (from, to) = (pair[0], pair[1]);
+            let action = match (to.x.cmp(&from.x), to.y.cmp(&from.y)) {
+                (Ordering::Greater, _) => "move right",
+                (Ordering::Less, _) => "move left",
+                (_, Ordering::Greater) => "move down",
+                (_, Ordering::Less) => "move up",
+                _ => "wait",
             };
-            blizzards.check_and_add(&mut queue, new)
+            write!(f, "\nminute {}: {action}", to.step)?;
         }
-        if state.y < blizzards.height + 1 {
-            let new = State {
-                step: state.step + 1,
-                y: state.y + 1,
-                ..state
-            };
-            blizzards.check_and_add(&mut queue, new)
-        }
-        if state.y > 0 {
-            let new = State {
-                step: state.step + 1,
-                y: state.y - 1,
-                ..state
-            };
-            blizzards.check_and_add(&mut queue, new)
-        }
-        let new = State {
-            step: state.step + 1,
-            ..state
-        };
-        if !blizzards.blizzed(new) {
-            queue.push_back(new);
+        Ok(())
+    }
+}
+
+impl Blizzards {
+    /// The valley as the puzzle draws it, a minute into the trip
+    fn draw(&self, expedition: State) -> Frame {
+        let mut frame = Frame::new(self.width + 2, self.height + 2, b'#');
+        frame[(1, 0)] = b'.';
+        frame[(self.width, self.height + 1)] = b'.';
+        let (width, height, step) = (
+            self.width as isize,
+            self.height as isize,
+            expedition.step as isize,
+        );
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let wrap = |dx: isize, dy: isize| {
+                    (
+                        (x as isize + dx).rem_euclid(width) as usize,
+                        (y as isize + dy).rem_euclid(height) as usize,
+                    )
+                };
+                let here: Vec<u8> = [
+                    (self.right[wrap(-step, 0)], b'>'),
+                    (self.left[wrap(step, 0)], b'<'),
+                    (self.down[wrap(0, -step)], b'v'),
+                    (self.up[wrap(0, step)], b'^'),
+                ]
+                .into_iter()
+                .filter_map(|(present, c)| present.then_some(c))
+                .collect();
+                frame[(x + 1, y + 1)] = match here[..] {
+                    [] => b'.',
+                    [c] => c,
+                    _ => b'0' + here.len() as u8,
+                };
+            }
         }
+        frame[(expedition.x + 1, expedition.y)] = b'E';
+        frame
+    }
+
+    fn entrance(&self) -> (usize, usize) {
+        (0, 0)
+    }
+
+    fn exit(&self) -> (usize, usize) {
+        (self.width - 1, self.height + 1)
     }
-    unreachable!("cannot reach ,,,")
 }
 
 #[aoc(day24, part1)]
 fn one_way(blizzards: &Blizzards) -> usize {
-    pathfind(
-        blizzards,
-        State {
-            step: 0,
-            x: 0,
-            y: 0,
-        },
-        (blizzards.width - 1, blizzards.height + 1),
-    )
-    .step
+    explain_one_way(blizzards).minutes()
+}
+
+#[aoc(day24, part1, explain)]
+fn explain_one_way(blizzards: &Blizzards) -> Trip {
+    Trip::new(blizzards, &[blizzards.exit()])
 }
 
 #[aoc(day24, part2)]
 fn three_way(blizzards: &Blizzards) -> usize {
-    let first_step = pathfind(
-        blizzards,
-        State {
-            step: 0,
-            x: 0,
-            y: 0,
-        },
-        (blizzards.width - 1, blizzards.height + 1),
-    );
-    let second_step = pathfind(blizzards, first_step, (0, 0));
-    pathfind(
-        blizzards,
-        second_step,
-        (blizzards.width - 1, blizzards.height + 1),
-    )
-    .step
+    explain_three_way(blizzards).minutes()
+}
+
+#[aoc(day24, part2, explain)]
+fn explain_three_way(blizzards: &Blizzards) -> Trip {
+    let goals = [blizzards.exit(), blizzards.entrance(), blizzards.exit()];
+    Trip::new(blizzards, &goals)
+}
+
+/// Records a frame for each minute of the trip
+pub(crate) fn visualize(input: &str, part: u32, recorder: &mut Recorder) -> Result<(), ParseError> {
+    let blizzards = generator(input)?;
+    let trip = if part == 2 {
+        explain_three_way(&blizzards)
+    } else {
+        explain_one_way(&blizzards)
+    };
+    for &state in &trip.states {
+        recorder.record(|| blizzards.draw(state));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day24.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(one_way(&input), 18);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(three_way(&input), 54);
+    }
+
+    #[test]
+    fn trip() {
+        let input = generator(EXAMPLE).unwrap();
+        let trip = explain_three_way(&input);
+        assert_eq!(trip.states.len(), 55);
+        for pair in trip.states.windows(2) {
+            assert!(input.moves(pair[0]).any(|state| state == pair[1]));
+        }
+    }
 }
@@ -1,3 +1,8 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 const fn geom(i: u32, base: i64) -> i64 {
     base.pow(i) + (if i != 0 { geom(i - 1, base) } else { 0 })
 }
@@ -6,29 +11,34 @@ const fn geom(i: u32, base: i64) -> i64 {
 const MAX_EXP: u32 = 26;
 const BALANCER: i64 = 2 * geom(MAX_EXP, 5);
 
-#[aoc(day25, part1)]
-fn entry(input: &str) -> String {
-    let sum = input
+#[aoc_generator(day25)]
+fn generator(input: &str) -> Result<Vec<i64>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(25, input);
+    input
         .lines()
         .map(|line| {
-            line.bytes()
-                .scan(0, |a, b| {
-                    *a *= 5;
-                    *a += match b {
-                        b'2' => 2,
-                        b'1' => 1,
-                        b'0' => 0,
-                        b'-' => -1,
-                        b'=' => -2,
-                        _ => unreachable!(),
-                    };
-                    Some(*a)
-                })
-                .last()
-                .unwrap()
+            if line.is_empty() {
+                return Err(ctx.error(line, "a SNAFU number"));
+            }
+            line.char_indices().try_fold(0, |a, (i, c)| {
+                let digit = match c {
+                    '2' => 2,
+                    '1' => 1,
+                    '0' => 0,
+                    '-' => -1,
+                    '=' => -2,
+                    _ => return Err(ctx.error(&line[i..], "a SNAFU digit")),
+                };
+                Ok(a * 5 + digit)
+            })
         })
-        .sum::<i64>()
-        + BALANCER;
+        .collect()
+}
+
+#[aoc(day25, part1)]
+fn entry(input: &[i64]) -> String {
+    let sum = input.iter().sum::<i64>() + BALANCER;
 
     String::from_utf8(
         (0..=MAX_EXP)
@@ -57,3 +67,16 @@ fn entry(input: &str) -> String {
     .trim_start_matches('0')
     .to_string()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day25.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(entry(&input), "2=-1=0");
+    }
+}
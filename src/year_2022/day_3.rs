@@ -1,3 +1,8 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 fn priority(c: &u8) -> u64 {
     let lower = c & 0b100000;
     let offset = c & 0b11111;
@@ -13,14 +18,20 @@ fn reduce(bits: &[u64]) -> u64 {
 }
 
 #[aoc_generator(day3)]
-fn generator(input: &str) -> Vec<Vec<u64>> {
+fn generator(input: &str) -> Result<Vec<Vec<u64>>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(3, input);
     input
         .lines()
         .map(|line| {
-            line.as_bytes()
-                .iter()
-                .map(priority)
-                .map(|c| 1 << c)
+            line.char_indices()
+                .map(|(i, c)| {
+                    if c.is_ascii_alphabetic() {
+                        Ok(1 << priority(&(c as u8)))
+                    } else {
+                        Err(ctx.error(&line[i..], "an item letter"))
+                    }
+                })
                 .collect()
         })
         .collect()
@@ -53,3 +64,22 @@ fn max_within_groups(input: &[Vec<u64>]) -> u32 {
         })
         .sum()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day3.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(max_within_line(&input), 157);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(max_within_groups(&input), 70);
+    }
+}
@@ -1,51 +1,70 @@
-struct Range {
-    start: u32,
-    end: u32,
-}
-
-impl Range {
-    fn from_str(s: &str) -> Self {
-        let (start, end) = s.split_once('-').unwrap();
-        Self {
-            start: start.parse().unwrap(),
-            end: end.parse().unwrap(),
-        }
-    }
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+    interval::Interval,
+};
 
-    fn contains(&self, other: &Range) -> bool {
-        self.start <= other.start && self.end >= other.end
-    }
+/// The sections an elf is assigned, as written: `2-4` is sections 2, 3 and 4
+type Sections = Interval<u32>;
 
-    fn overlaps_left(&self, other: &Range) -> bool {
-        self.start <= other.start && self.end >= other.start
+fn parse_sections(ctx: &ParseContext, s: &str) -> Result<Sections, ParseError> {
+    let (start, end) = ctx.split_once(s, "-")?;
+    let (start, end) = (ctx.number(start)?, ctx.number(end)?);
+    if start > end {
+        return Err(ctx.error(s, "a range that doesn't end before it starts"));
     }
+    Ok(Interval::new(start, end))
 }
 
 #[aoc_generator(day4)]
-fn generator(input: &str) -> Vec<(Range, Range)> {
+fn generator(input: &str) -> Result<Vec<(Sections, Sections)>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(4, input);
     input
         .lines()
         .map(|line| {
-            let (l, r) = line.split_once(',').unwrap();
-            let left = Range::from_str(l);
-            let right = Range::from_str(r);
-            (left, right)
+            let (l, r) = ctx.split_once(line, ",")?;
+            let left = parse_sections(&ctx, l)?;
+            let right = parse_sections(&ctx, r)?;
+            Ok((left, right))
         })
         .collect()
 }
 
 #[aoc(day4, part1)]
-fn containment(input: &[(Range, Range)]) -> usize {
+fn containment(input: &[(Sections, Sections)]) -> usize {
     input
         .iter()
-        .filter(|(l, r)| l.contains(r) || r.contains(l))
+        .filter(|(l, r)| l.contains_interval(r) || r.contains_interval(l))
         .count()
 }
 
 #[aoc(day4, part2)]
-fn overlap(input: &[(Range, Range)]) -> usize {
-    input
-        .iter()
-        .filter(|(l, r)| l.overlaps_left(r) || r.overlaps_left(l))
-        .count()
+fn overlap(input: &[(Sections, Sections)]) -> usize {
+    input.iter().filter(|(l, r)| l.overlaps(r)).count()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day4.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(containment(&input), 2);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(overlap(&input), 4);
+    }
+
+    #[test]
+    fn backwards() {
+        let error = generator("2-4,6-8\n5-3,1-2\n").unwrap_err();
+        assert!(error.to_string().contains("5-3"), "{error}");
+    }
 }
@@ -1,5 +1,10 @@
 use std::cmp::Ordering;
 
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 #[derive(Debug)]
 struct Instruction {
     count: usize,
@@ -21,8 +26,10 @@ fn debug(stacks: &[Vec<u8>]) {
 }
 
 #[aoc_generator(day5)]
-fn generator(input: &str) -> Supplies {
-    let (init, instrs) = input.split_once("\n\n").unwrap();
+fn generator(input: &str) -> Result<Supplies, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(5, input);
+    let (init, instrs) = ctx.split_once(input, "\n\n")?;
     // from bottom up, ignoring the reference row
     let mut stacks = vec![];
     for line in init.lines().rev().skip(1) {
@@ -36,25 +43,37 @@ fn generator(input: &str) -> Supplies {
             }
         }
         for i in 0..count {
-            if bytes[i * 4 + 1] != b' ' {
-                stacks[i].push(bytes[i * 4 + 1]);
+            match bytes[i * 4 + 1] {
+                b' ' => (),
+                b'A'..=b'Z' => stacks[i].push(bytes[i * 4 + 1]),
+                _ => return Err(ctx.error(&line[i * 4 + 1..], "a crate letter")),
             }
         }
     }
     let mut instructions = vec![];
     for line in instrs.lines() {
-        let [_, count, _, src, _, dest] = line.split_ascii_whitespace().collect::<Vec<_>>()[..] else {unreachable!("bad line")};
+        let [_, count, _, src, _, dest] = line.split_ascii_whitespace().collect::<Vec<_>>()[..]
+        else {
+            return Err(ctx.error(line, "`move <n> from <stack> to <stack>`"));
+        };
+        let stack = |token| match ctx.number::<usize>(token)? {
+            n if (1..=stacks.len()).contains(&n) => Ok(n - 1),
+            _ => Err(ctx.error(
+                token,
+                format!("a stack number between 1 and {}", stacks.len()),
+            )),
+        };
         instructions.push(Instruction {
-            count: count.parse().unwrap(),
-            from: src.parse::<usize>().unwrap() - 1,
-            to: dest.parse::<usize>().unwrap() - 1,
+            count: ctx.number(count)?,
+            from: stack(src)?,
+            to: stack(dest)?,
         });
     }
 
-    Supplies {
+    Ok(Supplies {
         stacks,
         instructions,
-    }
+    })
 }
 
 #[aoc(day5, part1)]
@@ -93,3 +112,22 @@ fn repeated_stack_top(input: &Supplies) -> String {
     let tops: Vec<_> = stacks.iter_mut().map(|s| s.pop().unwrap()).collect();
     String::from_utf8(tops).unwrap()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day5.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(iterated_stack_top(&input), "CMZ");
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(repeated_stack_top(&input), "MCD");
+    }
+}
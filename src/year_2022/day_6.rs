@@ -1,6 +1,22 @@
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 #[aoc_generator(day6)]
-fn generator(input: &[u8]) -> Vec<u32> {
-    input.iter().map(|c| 1 << ((*c - b'a') as u32)).collect()
+fn generator(input: &str) -> Result<Vec<u32>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(6, input);
+    input
+        .char_indices()
+        .map(|(i, c)| {
+            if c.is_ascii_lowercase() {
+                Ok(1 << (c as u8 - b'a'))
+            } else {
+                Err(ctx.error(&input[i..], "a lowercase letter"))
+            }
+        })
+        .collect()
 }
 
 fn bithash(slice: &[u32]) -> u32 {
@@ -50,3 +66,34 @@ fn with_rolling_hash_4(input: &[u32]) -> usize {
 fn with_rolling_hash_14(input: &[u32]) -> usize {
     rolling_hash(input, 14)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day6.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(with_regular_hash_4(&input), 7);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(with_regular_hash_14(&input), 19);
+    }
+
+    #[test]
+    fn part_1_rolling() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(with_rolling_hash_4(&input), 7);
+    }
+
+    #[test]
+    fn part_2_rolling() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(with_rolling_hash_14(&input), 19);
+    }
+}
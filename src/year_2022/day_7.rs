@@ -1,5 +1,10 @@
 use std::collections::HashMap;
 
+use crate::{
+    error::{ParseContext, ParseError},
+    input::normalize,
+};
+
 enum Node {
     File(u64),
     // size is populated after construction
@@ -11,18 +16,38 @@ enum Node {
 }
 
 #[aoc_generator(day7)]
-fn generator(input: &str) -> Node {
+fn generator(input: &str) -> Result<Node, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(7, input);
     let mut tree = Node::Directory {
         children: HashMap::new(),
         parent: std::ptr::null_mut(),
         size: 0,
     };
     let mut cwd = &mut tree;
-    for cmd in input.split("$ ").skip(1) {
+    let commands = ctx.strip_prefix(input, "$ ")?;
+    for cmd in commands.split("\n$ ") {
         let mut lines = cmd.lines();
-        let input = lines.next().expect("No command");
-        match input.split_once(' ') {
-            Some((_, dir)) => match dir {
+        let input = lines.next().unwrap_or(cmd);
+        match input {
+            "ls" => {
+                let parent = cwd as *mut Node;
+                if let Node::Directory { children, .. } = cwd {
+                    for output in lines {
+                        let (prefix, filename) = ctx.split_once(output, " ")?;
+                        let child = match prefix {
+                            "dir" => Node::Directory {
+                                children: HashMap::new(),
+                                parent,
+                                size: 0,
+                            },
+                            size => Node::File(ctx.number(size)?),
+                        };
+                        children.insert(filename.to_string(), child);
+                    }
+                };
+            }
+            _ => match ctx.strip_prefix(input, "cd ")? {
                 "/" => cwd = &mut tree,
                 ".." => {
                     if let Node::Directory { parent, .. } = *cwd {
@@ -33,31 +58,16 @@ fn generator(input: &str) -> Node {
                 }
                 folder => {
                     if let Node::Directory { children, .. } = cwd {
-                        cwd = children.get_mut(folder).expect("directory not found");
+                        cwd = children
+                            .get_mut(folder)
+                            .ok_or_else(|| ctx.error(folder, "a directory listed by `ls`"))?;
                     }
                 }
             },
-            None => {
-                let parent = cwd as *mut Node;
-                if let Node::Directory { children, .. } = cwd {
-                    children.extend(lines.map(|output| {
-                        let (prefix, filename) = output.split_once(' ').expect("Bad ls output");
-                        let child = match prefix.parse::<u64>() {
-                            Ok(size) => Node::File(size),
-                            Err(_) => Node::Directory {
-                                children: HashMap::new(),
-                                parent,
-                                size: 0,
-                            },
-                        };
-                        (filename.to_string(), child)
-                    }));
-                };
-            }
         }
     }
     count_sizes(&mut tree);
-    tree
+    Ok(tree)
 }
 
 fn count_sizes(input: &mut Node) -> u64 {
@@ -112,3 +122,22 @@ fn best_removable_dir(tree: &Node) -> u64 {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day7.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(removable_dirs(&input), 95437);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(best_removable_dir(&input), 24933642);
+    }
+}
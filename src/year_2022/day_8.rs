@@ -1,74 +1,40 @@
-struct Grid {
-    elements: Vec<u8>,
-    width: usize,
-    height: usize,
-}
+use crate::{
+    error::{ParseContext, ParseError},
+    grid::Grid,
+    input::normalize,
+};
 
 #[aoc_generator(day8)]
-fn generator(input: &str) -> Grid {
-    let mut elements = vec![];
-    let mut height = 0;
-    for line in input.lines() {
-        height += 1;
-        elements.extend(line.as_bytes().iter().map(|n| n - b'0'));
-    }
-    let width = elements.len() / height;
-    Grid {
-        elements,
-        width,
-        height,
-    }
+fn generator(input: &str) -> Result<Grid<u8>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(8, input);
+    Grid::parse(&ctx, input, "a tree height", |c| {
+        c.is_ascii_digit().then(|| c - b'0')
+    })
 }
 
-enum Axis {
-    Horizontal,
-    Vertical,
-}
-
-fn set_visible_axis(
-    visible: &mut [u32],
-    Grid {
-        elements,
-        width,
-        height,
-    }: &Grid,
-    axis: Axis,
-) {
-    let width = *width;
-    let height = *height;
-
-    let (major, minor) = match axis {
-        Axis::Vertical => (width, height),
-        Axis::Horizontal => (height, width),
-    };
-
-    for i in 0..major {
-        let mut top = 0u8;
+/// Marks the trees visible from the left or right of each row
+fn set_visible_rows(visible: &mut Grid<bool>, grid: &Grid<u8>) {
+    for (row, visible) in grid.rows().zip(visible.rows_mut()) {
+        // edge trees are visible even at height 0
+        let mut top = None;
         let mut top_j = 0;
-        for j in 0..minor {
-            let index = match axis {
-                Axis::Horizontal => i * minor + j,
-                Axis::Vertical => j * minor + i,
-            };
-            if elements[index] > top {
-                top = elements[index];
+        for (j, &tree) in row.iter().enumerate() {
+            if Some(tree) > top {
+                top = Some(tree);
                 top_j = j;
-                visible[index] = 1;
-                if elements[index] == b'9' {
+                visible[j] = true;
+                if tree == 9 {
                     break; // no higher digits
                 }
             }
         }
-        let mut top_rev = 0;
-        for j in 0..minor - 1 - top_j {
-            let index = match axis {
-                Axis::Horizontal => i * minor + minor - 1 - j,
-                Axis::Vertical => (major - 1 - j) * minor + i,
-            };
-            if elements[index] > top_rev {
-                top_rev = elements[index];
-                visible[index] = 1;
-                if elements[index] == top {
+        let mut top_rev = None;
+        for j in (top_j + 1..row.len()).rev() {
+            if Some(row[j]) > top_rev {
+                top_rev = Some(row[j]);
+                visible[j] = true;
+                if top_rev == top {
                     break; // no higher digits
                 }
             }
@@ -77,9 +43,9 @@ fn set_visible_axis(
 }
 
 #[allow(unused)]
-fn debug_results(visible: &[u32], width: usize) {
+fn debug_results(scores: &Grid<u32>) {
     // Draws a pretty grid
-    for row in visible.chunks(width) {
+    for row in scores.rows() {
         for c in row {
             let s = match *c {
                 0 => ' ',
@@ -97,54 +63,66 @@ fn debug_results(visible: &[u32], width: usize) {
 }
 
 #[aoc(day8, part1)]
-fn count_visible(grid: &Grid) -> u32 {
-    let mut visible = vec![0_u32; grid.elements.len()];
-    set_visible_axis(&mut visible, grid, Axis::Horizontal);
-    set_visible_axis(&mut visible, grid, Axis::Vertical);
-    visible.iter().sum()
-}
-
-enum Direction {
-    Right,
-    Left,
-    Up,
-    Down,
+fn count_visible(grid: &Grid<u8>) -> usize {
+    let mut visible = Grid::new(grid.width(), grid.height(), false);
+    set_visible_rows(&mut visible, grid);
+    // columns are the rows of the transposed grid
+    let mut visible = visible.transpose();
+    set_visible_rows(&mut visible, &grid.transpose());
+    visible.iter().filter(|&(_, &visible)| visible).count()
 }
-use Direction::*;
 
-fn set_scores_direction(scores: &mut [u32], grid: &Grid, direction: Direction) {
-    let (major, minor) = match direction {
-        Right | Left => (grid.height, grid.width),
-        Up | Down => (grid.width, grid.height),
-    };
-
-    for i in 1..major - 1 {
-        let mut counts = [1; 10];
-        for j in 1..minor - 1 {
-            let index = match direction {
-                Right => i * minor + j,
-                Left => i * minor + minor - 1 - j,
-                Down => j * minor + i,
-                Up => (minor - 1 - j) * minor + i,
-            };
-            scores[index] *= counts[grid.elements[index] as usize];
-            for k in 0..=grid.elements[index] {
-                counts[k as usize] = 1;
-            }
-            for k in grid.elements[index] + 1..10 {
-                counts[k as usize] += 1;
+/// Multiplies in the viewing distance to the left and right of each tree
+fn set_scores_rows(scores: &mut Grid<u32>, grid: &Grid<u8>) {
+    for (row, scores) in grid.rows().zip(scores.rows_mut()) {
+        let width = row.len();
+        // one of the viewing distances of an edge tree is always 0
+        scores[0] = 0;
+        scores[width - 1] = 0;
+        for order in [
+            &mut (1..width - 1) as &mut dyn Iterator<Item = usize>,
+            &mut (1..width - 1).rev(),
+        ] {
+            // number of trees seen by a tree of each height
+            let mut counts = [1; 10];
+            for j in order {
+                let tree = row[j] as usize;
+                scores[j] *= counts[tree];
+                for count in &mut counts[..=tree] {
+                    *count = 1;
+                }
+                for count in &mut counts[tree + 1..] {
+                    *count += 1;
+                }
             }
         }
     }
 }
 
 #[aoc(day8, part2)]
-fn best_spot(grid: &Grid) -> u32 {
-    let mut scores = vec![1_u32; grid.elements.len()];
-    set_scores_direction(&mut scores, grid, Right);
-    set_scores_direction(&mut scores, grid, Left);
-    set_scores_direction(&mut scores, grid, Down);
-    set_scores_direction(&mut scores, grid, Up);
+fn best_spot(grid: &Grid<u8>) -> u32 {
+    let mut scores = Grid::new(grid.width(), grid.height(), 1_u32);
+    set_scores_rows(&mut scores, grid);
+    let mut scores = scores.transpose();
+    set_scores_rows(&mut scores, &grid.transpose());
+    scores.iter().map(|(_, &score)| score).max().unwrap_or(0)
+}
 
-    scores.into_iter().max().unwrap_or(0)
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day8.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(count_visible(&input), 21);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(best_spot(&input), 8);
+    }
 }
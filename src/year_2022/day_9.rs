@@ -1,99 +1,48 @@
 use fxhash::FxHashSet;
 
-#[derive(PartialEq, Eq, PartialOrd, Ord, Clone, Copy, Debug, Hash)]
-struct Vector2(i32, i32);
-
-impl Vector2 {
-    fn zero() -> Self {
-        Self(0, 0)
-    }
-    fn right(scale: i32) -> Self {
-        Self(scale, 0)
-    }
-    fn left(scale: i32) -> Self {
-        Self(-scale, 0)
-    }
-    fn down(scale: i32) -> Self {
-        Self(0, scale)
-    }
-    fn up(scale: i32) -> Self {
-        Self(0, -scale)
-    }
-    fn normalize(self) -> Self {
-        Self(self.0.signum(), self.1.signum())
-    }
-    fn norm(self) -> i32 {
-        self.0.abs().max(self.1.abs())
-    }
-}
-
-impl std::ops::Mul<i32> for Vector2 {
-    type Output = Self;
-
-    fn mul(self, rhs: i32) -> Self::Output {
-        Self(self.0 * rhs, self.1 * rhs)
-    }
-}
-
-impl std::ops::Add<Vector2> for Vector2 {
-    type Output = Self;
-
-    fn add(self, rhs: Self) -> Self::Output {
-        Self(self.0 + rhs.0, self.1 + rhs.1)
-    }
-}
-
-impl std::ops::AddAssign<Vector2> for Vector2 {
-    fn add_assign(&mut self, rhs: Vector2) {
-        *self = *self + rhs;
-    }
-}
-
-impl std::ops::Sub<Vector2> for Vector2 {
-    type Output = Self;
-
-    fn sub(self, rhs: Self) -> Self::Output {
-        Self(self.0 - rhs.0, self.1 - rhs.1)
-    }
-}
+use crate::{
+    error::{ParseContext, ParseError},
+    geometry::{Direction, Point2},
+    input::normalize,
+};
 
 #[aoc_generator(day9)]
-fn generator(input: &str) -> Vec<Vector2> {
+fn generator(input: &str) -> Result<Vec<Point2<i32>>, ParseError> {
+    let input: &str = &normalize(input);
+    let ctx = ParseContext::new(9, input);
     input
         .lines()
         .map(|line| {
-            let bytes = line.as_bytes();
-            let direction = bytes[0];
-            let scale = bytes[2..]
-                .iter()
-                .fold(0, |sum, digit| sum * 10 + digit - b'0') as i32;
-            match direction {
-                b'R' => Vector2::right(scale),
-                b'L' => Vector2::left(scale),
-                b'U' => Vector2::up(scale),
-                b'D' => Vector2::down(scale),
-                _ => unreachable!(),
-            }
+            let (direction, scale) = ctx.split_once(line, " ")?;
+            let scale = ctx.number(scale)?;
+            let direction = match direction {
+                "R" => Direction::Right,
+                "L" => Direction::Left,
+                "U" => Direction::Up,
+                "D" => Direction::Down,
+                _ => return Err(ctx.error(direction, "one of `R`, `L`, `U` or `D`")),
+            };
+            Ok(direction.delta() * scale)
         })
         .collect()
 }
 
 #[aoc(day9, part1)]
-fn regular_tabby_cat(input: &[Vector2]) -> usize {
+fn regular_tabby_cat(input: &[Point2<i32>]) -> usize {
     multi_tail::<2>(input)
 }
 #[aoc(day9, part2)]
-fn cat_o_nine_tails(input: &[Vector2]) -> usize {
+fn cat_o_nine_tails(input: &[Point2<i32>]) -> usize {
     multi_tail::<10>(input)
 }
 
 // I'm using a const parameter because arrays are nice
-fn multi_tail<const KNOTS: usize>(input: &[Vector2]) -> usize {
+fn multi_tail<const KNOTS: usize>(input: &[Point2<i32>]) -> usize {
     let last = KNOTS - 1;
     let mut visited = FxHashSet::default();
-    visited.insert(Vector2::zero());
-    let mut knots = [Vector2::zero(); KNOTS];
-    let mut previous_tail = Vector2::zero();
+    visited.insert(Point2::default());
+    let mut knots = [Point2::default(); KNOTS];
+    let mut previous_tail = Point2::default();
     for delta in input.iter().copied() {
         // Skip the overhead of hashset insertion if the tail hasn't moved
         if previous_tail != knots[last] {
@@ -106,15 +55,15 @@ fn multi_tail<const KNOTS: usize>(input: &[Vector2]) -> usize {
             for joint in 0..last {
                 let head = knots[joint];
                 let tail = &mut knots[joint + 1];
-                if (head - *tail).norm() > 1 {
-                    let jerk = (head - *tail).normalize();
+                if (head - *tail).chebyshev() > 1 {
+                    let jerk = (head - *tail).signum();
                     *tail += jerk;
                     // The two knots are now aligned
                     let trail = head - *tail;
-                    let step = trail.normalize();
+                    let step = trail.signum();
 
                     if joint == last - 1 {
-                        for i in 0..trail.norm() {
+                        for i in 0..trail.chebyshev() {
                             visited.insert(*tail + step * i);
                         }
                     }
@@ -129,8 +78,8 @@ fn multi_tail<const KNOTS: usize>(input: &[Vector2]) -> usize {
         // Finer motion resolution is required to simulate
         // the universe with multiple knots
         if KNOTS > 2 {
-            let step = delta.normalize();
-            for _ in 0..delta.norm() {
+            let step = delta.signum();
+            for _ in 0..delta.chebyshev() {
                 simulation_step(step);
             }
         } else {
@@ -139,3 +88,25 @@ fn multi_tail<const KNOTS: usize>(input: &[Vector2]) -> usize {
     }
     visited.len()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE: &str = include_str!("../../input/2022/examples/day9.txt");
+    const LARGER_EXAMPLE: &str = include_str!("../../input/2022/examples/day9_larger.txt");
+
+    #[test]
+    fn part_1() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(regular_tabby_cat(&input), 13);
+    }
+
+    #[test]
+    fn part_2() {
+        let input = generator(EXAMPLE).unwrap();
+        assert_eq!(cat_o_nine_tails(&input), 1);
+        let input = generator(LARGER_EXAMPLE).unwrap();
+        assert_eq!(cat_o_nine_tails(&input), 36);
+    }
+}
@@ -1,20 +1,20 @@
 mod day_1;
-mod day_10;
-mod day_11;
-mod day_12;
-mod day_13;
-mod day_14;
-mod day_15;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
 mod day_16;
-mod day_17;
+pub(crate) mod day_17;
 mod day_18;
 mod day_19;
 mod day_2;
 mod day_20;
 mod day_21;
 mod day_22;
-mod day_23;
-mod day_24;
+pub(crate) mod day_23;
+pub(crate) mod day_24;
 mod day_25;
 mod day_3;
 mod day_4;
@@ -0,0 +1,17 @@
+use aoc22::{
+    solutions::SOLUTIONS,
+    verify::{self, Answers, ANSWERS_PATH},
+};
+
+#[test]
+#[ignore = "solves every day on its full input, which wants --release"]
+fn recorded_answers() {
+    let answers = Answers::load(ANSWERS_PATH).unwrap();
+    let outcomes: Vec<_> = verify::verify(SOLUTIONS, &answers).collect();
+    let table = verify::table(&outcomes);
+    println!("{table}");
+    assert!(
+        !outcomes.iter().any(|o| o.status.is_failure()),
+        "some solutions didn't match their recorded answer:\n{table}"
+    );
+}